@@ -1,88 +1,300 @@
-#![feature(test)]
-
-extern crate test;
-extern crate core;
+#[macro_use]
+extern crate criterion;
 extern crate rand_core;
 extern crate small_rngs;
 
 const RAND_BENCH_N: u64 = 1000;
 
-use core::mem::size_of_val;
-use test::{black_box, Bencher};
+use std::time::Duration;
+use criterion::{black_box, BenchmarkGroup, BenchmarkId, Criterion, Throughput};
+use criterion::measurement::WallTime;
 
-use rand_core::{SeedableRng, Rng};
+use rand_core::{SeedableRng, RngCore};
 use small_rngs::*;
 
-macro_rules! gen_uint {
-    ($fnn:ident, $gen:ident, $rng:ident) => {
-        #[bench]
-        fn $fnn(b: &mut Bencher) {
-            let mut master_rng =
-                XoroshiroMt64of128Rng::from_seed([236, 186, 13, 169, 36, 22, 113, 213,
-                                                  12, 21, 28, 253, 104, 247, 90, 186]);
-            let mut rng = $rng::from_rng(&mut master_rng).unwrap();
-            b.bytes = size_of_val(&rng.$gen()) as u64 * RAND_BENCH_N;
-            b.iter(|| {
-                for _ in 0..RAND_BENCH_N {
-                    black_box(rng.$gen());
+fn master_rng() -> XoroshiroMt64of128Rng {
+    XoroshiroMt64of128Rng::from_seed([236, 186, 13, 169, 36, 22, 113, 213,
+                                      12, 21, 28, 253, 104, 247, 90, 186])
+}
+
+type Group<'a> = BenchmarkGroup<'a, WallTime>;
+
+/// A benchmark that is run for every generator. `new` creates a new instance
+/// of the generator, with a different seed every time.
+trait RngBench {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, new: F);
+}
+
+struct GenU32;
+
+impl RngBench for GenU32 {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rng = new();
+        group.bench_function(name, |b| b.iter(|| {
+            for _ in 0..RAND_BENCH_N {
+                black_box(rng.next_u32());
+            }
+        }));
+    }
+}
+
+struct GenU64;
+
+impl RngBench for GenU64 {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rng = new();
+        group.bench_function(name, |b| b.iter(|| {
+            for _ in 0..RAND_BENCH_N {
+                black_box(rng.next_u64());
+            }
+        }));
+    }
+}
+
+struct FillBytes(usize);
+
+impl RngBench for FillBytes {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rng = new();
+        let mut buf = vec![0u8; self.0];
+        group.bench_function(BenchmarkId::new(name, self.0), |b| b.iter(|| {
+            rng.fill_bytes(&mut buf);
+            black_box(&buf);
+        }));
+    }
+}
+
+fn from_seed<R: SeedableRng>(group: &mut Group, name: &str) {
+    let mut bytes = R::Seed::default();
+    master_rng().fill_bytes(bytes.as_mut());
+    group.bench_function(name, |b| b.iter(|| {
+        let mut seed = R::Seed::default();
+        seed.as_mut().copy_from_slice(black_box(bytes.as_mut()));
+        R::from_seed(seed)
+    }));
+}
+
+fn from_rng<R, F>(group: &mut Group, name: &str, new: F)
+    where F: Fn(&mut XoroshiroMt64of128Rng) -> R
+{
+    let mut master = master_rng();
+    group.bench_function(name, |b| b.iter(|| new(&mut master)));
+}
+
+/// A uniformly distributed integer in `0..range`, with Lemire's method: a
+/// widening multiply, and rejection of the part of the low word that is
+/// biased.
+fn gen_below<R: RngCore>(rng: &mut R, range: u32) -> u32 {
+    let mut m = rng.next_u32() as u64 * range as u64;
+    if (m as u32) < range {
+        let threshold = range.wrapping_neg() % range;
+        while (m as u32) < threshold {
+            m = rng.next_u32() as u64 * range as u64;
+        }
+    }
+    (m >> 32) as u32
+}
+
+/// Ranges for `gen_range`: a small one, and one that rejects almost half of
+/// the samples.
+const RANGES: [u32; 2] = [6, 0x8000_0001];
+
+struct GenRange(u32);
+
+impl RngBench for GenRange {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rng = new();
+        let range = self.0;
+        group.bench_function(BenchmarkId::new(name, range), |b| b.iter(|| {
+            for _ in 0..RAND_BENCH_N {
+                black_box(gen_below(&mut rng, range));
+            }
+        }));
+    }
+}
+
+/// A chain of dependent calls: every output is combined with the result of
+/// the previous step, and used as an index into a table. The next step can't
+/// start before the output is known, so this measures latency instead of
+/// throughput.
+struct Dependent;
+
+impl RngBench for Dependent {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rng = new();
+        let mut table = vec![0u32; 1 << 12];
+        for x in table.iter_mut() {
+            *x = rng.next_u32();
+        }
+        let mut index = 0;
+        group.bench_function(name, |b| b.iter(|| {
+            for _ in 0..RAND_BENCH_N {
+                index = table[((rng.next_u32() ^ index) & 0xfff) as usize];
+            }
+            black_box(index);
+        }));
+    }
+}
+
+/// A random walk on a 64×64 torus, with two bits of an output per step, and
+/// a count of the visits to every cell.
+struct RandomWalk;
+
+impl RngBench for RandomWalk {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rng = new();
+        let mut visits = vec![0u32; 64 * 64];
+        let (mut x, mut y) = (0usize, 0usize);
+        group.bench_function(name, |b| b.iter(|| {
+            for _ in 0..RAND_BENCH_N {
+                match rng.next_u32() >> 30 {
+                    0 => x = (x + 1) & 63,
+                    1 => x = (x + 63) & 63,
+                    2 => y = (y + 1) & 63,
+                    _ => y = (y + 63) & 63,
+                }
+                visits[y * 64 + x] += 1;
+            }
+            black_box(&visits);
+        }));
+    }
+}
+
+/// A Fisher-Yates shuffle of `RAND_BENCH_N` elements.
+struct Shuffle;
+
+impl RngBench for Shuffle {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rng = new();
+        let mut values: Vec<u32> = (0..RAND_BENCH_N as u32).collect();
+        group.bench_function(name, |b| b.iter(|| {
+            for i in (1..values.len()).rev() {
+                let j = gen_below(&mut rng, i as u32 + 1) as usize;
+                values.swap(i, j);
+            }
+            black_box(&values);
+        }));
+    }
+}
+
+/// A number of instances of which every one is used in turn, like an RNG per
+/// object of a simulation, compared with a single instance. The state of
+/// every instance has to be loaded and stored for every call.
+struct Instances(usize);
+
+impl RngBench for Instances {
+    fn run<R: RngCore, F: FnMut() -> R>(&self, group: &mut Group, name: &str, mut new: F) {
+        let mut rngs: Vec<R> = (0..self.0).map(|_| new()).collect();
+        let mut i = 0;
+        group.bench_function(BenchmarkId::new(name, self.0), |b| b.iter(|| {
+            let mut acc = 0;
+            for _ in 0..RAND_BENCH_N {
+                acc ^= rngs[i].next_u32();
+                i += 1;
+                if i == rngs.len() {
+                    i = 0;
                 }
-            });
+            }
+            black_box(acc);
+        }));
+    }
+}
+
+// All generators of the registry.
+macro_rules! benches {
+    ($($name:ident: $rng:ident $props:tt),* $(,)*) => {
+        fn for_all_rngs<B: RngBench>(group: &mut Group, bench: B) {
+            let mut master = master_rng();
+            $( bench.run(group, stringify!($name),
+                         || $rng::from_rng(&mut master).unwrap()); )*
+        }
+
+        fn bench_gen_u32(c: &mut Criterion) {
+            let mut group = c.benchmark_group("gen_u32");
+            group.throughput(Throughput::Bytes(4 * RAND_BENCH_N));
+            for_all_rngs(&mut group, GenU32);
+            group.finish();
+        }
+
+        fn bench_gen_u64(c: &mut Criterion) {
+            let mut group = c.benchmark_group("gen_u64");
+            group.throughput(Throughput::Bytes(8 * RAND_BENCH_N));
+            for_all_rngs(&mut group, GenU64);
+            group.finish();
+        }
+
+        fn bench_fill_bytes(c: &mut Criterion) {
+            let mut group = c.benchmark_group("fill_bytes");
+            for &size in [1 << 10, 1 << 16, 1 << 20].iter() {
+                group.throughput(Throughput::Bytes(size as u64));
+                for_all_rngs(&mut group, FillBytes(size));
+            }
+            group.finish();
+        }
+
+        fn bench_from_seed(c: &mut Criterion) {
+            let mut group = c.benchmark_group("from_seed");
+            $( from_seed::<$rng>(&mut group, stringify!($name)); )*
+            group.finish();
+        }
+
+        fn bench_from_rng(c: &mut Criterion) {
+            let mut group = c.benchmark_group("from_rng");
+            $( from_rng(&mut group, stringify!($name),
+                        |master| $rng::from_rng(master).unwrap()); )*
+            group.finish();
+        }
+
+        fn bench_gen_range(c: &mut Criterion) {
+            let mut group = c.benchmark_group("gen_range");
+            group.throughput(Throughput::Elements(RAND_BENCH_N));
+            for &range in RANGES.iter() {
+                for_all_rngs(&mut group, GenRange(range));
+            }
+            group.finish();
+        }
+
+        fn bench_dependent(c: &mut Criterion) {
+            let mut group = c.benchmark_group("dependent");
+            group.throughput(Throughput::Elements(RAND_BENCH_N));
+            for_all_rngs(&mut group, Dependent);
+            group.finish();
+        }
+
+        fn bench_random_walk(c: &mut Criterion) {
+            let mut group = c.benchmark_group("random_walk");
+            group.throughput(Throughput::Elements(RAND_BENCH_N));
+            for_all_rngs(&mut group, RandomWalk);
+            group.finish();
+        }
+
+        fn bench_shuffle(c: &mut Criterion) {
+            let mut group = c.benchmark_group("shuffle");
+            group.throughput(Throughput::Elements(RAND_BENCH_N));
+            for_all_rngs(&mut group, Shuffle);
+            group.finish();
+        }
+
+        fn bench_instances(c: &mut Criterion) {
+            let mut group = c.benchmark_group("instances");
+            group.throughput(Throughput::Bytes(4 * RAND_BENCH_N));
+            for &instances in [1, 16, 1024].iter() {
+                for_all_rngs(&mut group, Instances(instances));
+            }
+            group.finish();
         }
     }
 }
 
-gen_uint!(gen_u32_ci, next_u32, CiRng);
-gen_uint!(gen_u32_gj, next_u32, GjRng);
-gen_uint!(gen_u32_jsf32, next_u32, Jsf32Rng);
-gen_uint!(gen_u32_jsf64, next_u32, Jsf64Rng);
-gen_uint!(gen_u32_kiss32, next_u32, Kiss32Rng);
-gen_uint!(gen_u32_kiss64, next_u32, Kiss64Rng);
-gen_uint!(gen_u32_msws, next_u32, MswsRng);
-gen_uint!(gen_u32_mwp, next_u32, MwpRng);
-gen_uint!(gen_u32_pcg_xsh_64_lcg, next_u32, PcgXsh64LcgRng);
-gen_uint!(gen_u32_pcg_xsl_64_lcg, next_u32, PcgXsl64LcgRng);
-gen_uint!(gen_u32_pcg_xsl_128_mcg, next_u32, PcgXsl128McgRng);
-gen_uint!(gen_u32_sapparoth_32, next_u32, Sapparot32Rng);
-gen_uint!(gen_u32_sapparoth_64, next_u32, Sapparot64Rng);
-gen_uint!(gen_u32_sfc_32, next_u32, Sfc32Rng);
-gen_uint!(gen_u32_sfc_64, next_u32, Sfc64Rng);
-gen_uint!(gen_u32_velox, next_u32, Velox3bRng);
-gen_uint!(gen_u32_xorshift_128_32, next_u32, Xorshift128_32Rng);
-gen_uint!(gen_u32_xorshift_128_64, next_u32, Xorshift128_64Rng);
-gen_uint!(gen_u32_xorshift_128_plus, next_u32, Xorshift128PlusRng);
-gen_uint!(gen_u32_xorshift_mt_32, next_u32, XorshiftMt32Rng);
-gen_uint!(gen_u32_xorshift_mt_64, next_u32, XorshiftMt64Rng);
-gen_uint!(gen_u32_xoroshiro_128_plus, next_u32, Xoroshiro128PlusRng);
-gen_uint!(gen_u32_xoroshiro_64_plus, next_u32, Xoroshiro64PlusRng);
-gen_uint!(gen_u32_xoroshiro_mt_64of128, next_u32, XoroshiroMt64of128Rng);
-gen_uint!(gen_u32_xoroshiro_mt_32of128, next_u32, XoroshiroMt32of128Rng);
-gen_uint!(gen_u32_xsm32, next_u32, Xsm32Rng);
-gen_uint!(gen_u32_xsm64, next_u32, Xsm64Rng);
-
-gen_uint!(gen_u64_ci, next_u64, CiRng);
-gen_uint!(gen_u64_gj, next_u64, GjRng);
-gen_uint!(gen_u64_jsf32, next_u64, Jsf32Rng);
-gen_uint!(gen_u64_jsf64, next_u64, Jsf64Rng);
-gen_uint!(gen_u64_kiss32, next_u64, Kiss32Rng);
-gen_uint!(gen_u64_kiss64, next_u64, Kiss64Rng);
-gen_uint!(gen_u64_msws, next_u64, MswsRng);
-gen_uint!(gen_u64_mwp, next_u64, MwpRng);
-gen_uint!(gen_u64_sapparoth_32, next_u64, Sapparot32Rng);
-gen_uint!(gen_u64_sapparoth_64, next_u64, Sapparot64Rng);
-gen_uint!(gen_u64_sfc_32, next_u64, Sfc32Rng);
-gen_uint!(gen_u64_sfc_64, next_u64, Sfc64Rng);
-gen_uint!(gen_u64_pcg_xsh_64_lcg, next_u64, PcgXsh64LcgRng);
-gen_uint!(gen_u64_pcg_xsl_64_lcg, next_u64, PcgXsl64LcgRng);
-gen_uint!(gen_u64_pcg_xsl_128_mcg, next_u64, PcgXsl128McgRng);
-gen_uint!(gen_u64_velox, next_u64, Velox3bRng);
-gen_uint!(gen_u64_xorshift_128_32, next_u64, Xorshift128_32Rng);
-gen_uint!(gen_u64_xorshift_128_64, next_u64, Xorshift128_64Rng);
-gen_uint!(gen_u64_xorshift_128_plus, next_u64, Xorshift128PlusRng);
-gen_uint!(gen_u64_xorshift_mt_32, next_u64, XorshiftMt32Rng);
-gen_uint!(gen_u64_xorshift_mt_64, next_u64, XorshiftMt64Rng);
-gen_uint!(gen_u64_xoroshiro_128_plus, next_u64, Xoroshiro128PlusRng);
-gen_uint!(gen_u64_xoroshiro_64_plus, next_u64, Xoroshiro64PlusRng);
-gen_uint!(gen_u64_xoroshiro_mt_64of128, next_u64, XoroshiroMt64of128Rng);
-gen_uint!(gen_u64_xoroshiro_mt_32of128, next_u64, XoroshiroMt32of128Rng);
-gen_uint!(gen_u64_xsm32, next_u64, Xsm32Rng);
-gen_uint!(gen_u64_xsm64, next_u64, Xsm64Rng);
+generators!(benches);
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2));
+    targets = bench_gen_u32, bench_gen_u64, bench_fill_bytes, bench_from_seed,
+              bench_from_rng, bench_gen_range, bench_dependent,
+              bench_random_walk, bench_shuffle, bench_instances
+}
+criterion_main!(benches);
@@ -0,0 +1,156 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Avalanche test of the seeding of an RNG.
+//!
+//! Flipping a single bit of the seed should change every bit of every output
+//! with a probability of one half. For a number of random seeds, every bit of
+//! the seed is flipped in turn, and the first outputs of the RNG are compared
+//! with those of the original seed.
+//!
+//! The seeds are derived from a fixed sequence, so the results are the same
+//! from run to run, and can be compared when the seeding of an RNG changes.
+
+use rand_core::RngCore;
+use battery::Outcome;
+use registry::{self, Generator};
+use stats;
+
+/// The avalanche of one output of an RNG.
+#[derive(Clone, Debug)]
+pub struct OutputAvalanche {
+    /// The average fraction of the output bits that changed, ideally 0.5.
+    pub mean_distance: f64,
+    /// The largest deviation from 0.5 of the probability that an output bit
+    /// changes when a seed bit is flipped.
+    pub max_bias: f64,
+    /// The seed bit and output bit with the largest bias.
+    pub worst: (usize, u32),
+    /// The p-value of a chi-square test on the probability of every pair of
+    /// seed bit and output bit.
+    pub p: f64,
+}
+
+impl OutputAvalanche {
+    /// The p-value as an `Outcome` of the battery, to evaluate it.
+    pub fn outcome(&self, index: usize) -> Outcome {
+        Outcome { name: format!("output {}", index), p: self.p }
+    }
+}
+
+/// The avalanche of the first outputs of an RNG.
+#[derive(Clone, Debug)]
+pub struct Avalanche {
+    pub seed_bits: usize,
+    pub word_bits: u32,
+    /// The number of random seeds.
+    pub samples: usize,
+    pub outputs: Vec<OutputAvalanche>,
+}
+
+fn next_word(rng: &mut dyn RngCore, word_bits: u32) -> u64 {
+    if word_bits == 32 { rng.next_u32() as u64 } else { rng.next_u64() }
+}
+
+/// Run the avalanche test on the first `outputs` words of a generator, with
+/// `samples` random seeds.
+pub fn avalanche(generator: &Generator, outputs: usize, samples: usize)
+    -> Avalanche
+{
+    let seed_bits = generator.seed_bytes * 8;
+    let word_bits = generator.word_bits;
+    // counts[t][seed_bit * word_bits + out_bit]
+    let mut counts = vec![vec![0u32; seed_bits * word_bits as usize]; outputs];
+    let mut original = vec![0u64; outputs];
+
+    for sample in 0..samples {
+        let mut seed = registry::expand_seed(sample as u64, generator.seed_bytes);
+        {
+            let mut rng = (generator.from_seed)(&seed);
+            for x in original.iter_mut() {
+                *x = next_word(&mut *rng, word_bits);
+            }
+        }
+        for seed_bit in 0..seed_bits {
+            seed[seed_bit / 8] ^= 1 << (seed_bit % 8);
+            let mut rng = (generator.from_seed)(&seed);
+            seed[seed_bit / 8] ^= 1 << (seed_bit % 8);
+            for (t, x) in original.iter().enumerate() {
+                let mut diff = next_word(&mut *rng, word_bits) ^ x;
+                let row = &mut counts[t][seed_bit * word_bits as usize..];
+                while diff != 0 {
+                    let bit = diff.trailing_zeros() as usize;
+                    row[bit] += 1;
+                    diff &= diff - 1;
+                }
+            }
+        }
+    }
+
+    let n = samples as f64;
+    let outputs = counts.iter().map(|counts| {
+        let mut total = 0u64;
+        let mut max_bias = 0.0;
+        let mut worst = (0, 0);
+        let mut chi_square = 0.0;
+        for (i, &count) in counts.iter().enumerate() {
+            total += count as u64;
+            let bias = (count as f64 / n - 0.5).abs();
+            if bias > max_bias {
+                max_bias = bias;
+                worst = (i / word_bits as usize, (i % word_bits as usize) as u32);
+            }
+            let z = (count as f64 - n / 2.0) / (n / 4.0).sqrt();
+            chi_square += z * z;
+        }
+        OutputAvalanche {
+            mean_distance: total as f64 / (n * counts.len() as f64),
+            max_bias,
+            worst,
+            p: stats::chi_square_p(chi_square, counts.len() as f64),
+        }
+    }).collect();
+
+    Avalanche {
+        seed_bits,
+        word_bits,
+        samples,
+        outputs,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use battery::Evaluation;
+    use registry;
+    use super::*;
+
+    #[test]
+    fn test_avalanche_warm_up() {
+        // Sfc64Rng discards its first 18 outputs, after which every seed bit
+        // affects every output bit.
+        let result = avalanche(registry::find("sfc_64").unwrap(), 2, 1000);
+        for (i, output) in result.outputs.iter().enumerate() {
+            assert!(output.max_bias < 0.1, "{:?}", output);
+            assert!(output.outcome(i).evaluation() != Evaluation::Fail);
+        }
+    }
+
+    #[test]
+    fn test_avalanche_forced_bit() {
+        // PcgXsl128McgRng makes its state odd, so flipping bit 64 of the seed,
+        // the lowest bit of the state, does not change the output at all.
+        let result = avalanche(registry::find("pcg_xsl_128_mcg").unwrap(), 2, 100);
+        for (i, output) in result.outputs.iter().enumerate() {
+            assert_eq!(output.max_bias, 0.5);
+            assert_eq!(output.worst.0, 64);
+            assert_eq!(output.outcome(i).evaluation(), Evaluation::Fail);
+        }
+    }
+}
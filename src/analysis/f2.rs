@@ -0,0 +1,354 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Analysis of RNGs that are linear over GF(2).
+//!
+//! The state transition of the xorshift and xoroshiro generators is a linear
+//! map over GF(2): every bit of the next state is the xor of some bits of the
+//! current state. Such a generator has the maximal period of 2<sup>n</sup> - 1
+//! if and only if the characteristic polynomial of its transition matrix is
+//! primitive.
+//!
+//! The characteristic polynomial is found with the Berlekamp-Massey algorithm
+//! on 2n bits of one bit of the state. This gives the minimal polynomial of
+//! that sequence, which is the characteristic polynomial if it has degree n.
+//! If it has a lower degree, the characteristic polynomial is not irreducible,
+//! and the period is not maximal.
+
+use registry;
+use f2_linear::{mask, minimal_polynomial};
+
+pub use f2_linear::{F2LinearRng, Poly, mersenne_factors, jump_polynomial,
+                    jump_with};
+
+/// Call the macro `$m` with the list of all generators that implement
+/// `F2LinearRng`.
+///
+/// Every entry has the form `name: Type`, with the name of the generator in
+/// the registry.
+#[macro_export]
+macro_rules! f2_linear_generators {
+    ($m:ident) => {
+        $m! {
+            xorshift_128_32: Xorshift128_32Rng,
+            xorshift_128_64: Xorshift128_64Rng,
+            xorshift_128_plus: Xorshift128PlusRng,
+            xorshift_mt_32: XorshiftMt32Rng,
+            xorshift_mt_64: XorshiftMt64Rng,
+            xoroshiro_128_plus: Xoroshiro128PlusRng,
+            xoroshiro_64_plus: Xoroshiro64PlusRng,
+            xoroshiro_mt_64of128: XoroshiroMt64of128Rng,
+            xoroshiro_mt_32of128: XoroshiroMt32of128Rng
+        }
+    }
+}
+
+fn next_state<R: F2LinearRng>(state: u128) -> u128 {
+    let mut rng = R::from_state(state);
+    rng.step();
+    rng.state()
+}
+
+/// A square matrix over GF(2) of at most 128×128, with one `u128` per row.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Matrix {
+    rows: Vec<u128>,
+}
+
+impl Matrix {
+    /// The number of rows and columns.
+    pub fn size(&self) -> u32 {
+        self.rows.len() as u32
+    }
+
+    /// Row `i`, with bit `j` for the element in column `j`.
+    pub fn row(&self, i: u32) -> u128 {
+        self.rows[i as usize]
+    }
+
+    /// The product of the row vector `v` with this matrix.
+    pub fn left_apply(&self, v: u128) -> u128 {
+        let mut result = 0;
+        for (i, row) in self.rows.iter().enumerate() {
+            if (v >> i) & 1 != 0 {
+                result ^= row;
+            }
+        }
+        result
+    }
+
+    /// The product of this matrix with the column vector `v`.
+    pub fn apply(&self, v: u128) -> u128 {
+        let mut result = 0;
+        for (i, row) in self.rows.iter().enumerate() {
+            result |= ((row & v).count_ones() as u128 & 1) << i;
+        }
+        result
+    }
+}
+
+/// The transition matrix of an RNG: the state after one step is the matrix
+/// times the current state.
+pub fn transition_matrix<R: F2LinearRng>() -> Matrix {
+    let n = R::STATE_BITS;
+    let mut rows = vec![0u128; n as usize];
+    for j in 0..n {
+        let column = next_state::<R>(1 << j);
+        for (i, row) in rows.iter_mut().enumerate() {
+            *row |= ((column >> i) & 1) << j;
+        }
+    }
+    Matrix { rows }
+}
+
+/// Check that the transition of the RNG is really the linear map given by
+/// `matrix`, for a number of pseudo-random states.
+pub fn is_linear<R: F2LinearRng>(matrix: &Matrix) -> bool {
+    test_states(R::STATE_BITS, 100).iter().all(|&state| {
+        next_state::<R>(state) == matrix.apply(state)
+    })
+}
+
+/// Pseudo-random states, for checking linearity.
+fn test_states(bits: u32, count: usize) -> Vec<u128> {
+    registry::expand_seed(0, 16 * count).chunks(16).map(|chunk| {
+        chunk.iter().rev().fold(0u128, |acc, &b| acc << 8 | b as u128) & mask(bits)
+    }).collect()
+}
+
+/// The minimal polynomial of the matrix with respect to bit 0 of the state,
+/// starting from the state with only bit 0 set.
+pub fn characteristic_polynomial(matrix: &Matrix) -> Poly {
+    let n = matrix.size() as usize;
+    let mut bits = [0u64; 4];
+    let mut state = 1u128;
+    for i in 0..2 * n {
+        bits[i / 64] |= ((state & 1) as u64) << (i % 64);
+        state = matrix.apply(state);
+    }
+    minimal_polynomial(&bits, 2 * n)
+}
+
+/// The result of analysing an `F2LinearRng`.
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    /// The number of bits of the state.
+    pub state_bits: u32,
+    /// Whether the generator matches its transition matrix.
+    pub linear: bool,
+    /// The characteristic polynomial, if the minimal polynomial has full
+    /// degree.
+    pub polynomial: Poly,
+    /// Whether the polynomial is primitive, `None` if unknown.
+    pub primitive: Option<bool>,
+}
+
+impl Analysis {
+    /// Whether the period is 2^n - 1 for every nonzero state. `None` if this
+    /// could not be determined.
+    pub fn maximal_period(&self) -> Option<bool> {
+        if !self.linear {
+            None
+        } else if self.polynomial.degree() != self.state_bits {
+            Some(false)
+        } else {
+            self.primitive
+        }
+    }
+}
+
+/// Build the transition matrix of an RNG, and test its characteristic
+/// polynomial for primitivity.
+pub fn analyze<R: F2LinearRng>() -> Analysis {
+    let matrix = transition_matrix::<R>();
+    let linear = is_linear::<R>(&matrix);
+    let polynomial = characteristic_polynomial(&matrix);
+    let primitive = if polynomial.degree() == R::STATE_BITS {
+        polynomial.is_primitive()
+    } else {
+        Some(false)
+    };
+    Analysis {
+        state_bits: R::STATE_BITS,
+        linear,
+        polynomial,
+        primitive,
+    }
+}
+
+/// Bit `bit` of the output word of `R` in `state`.
+fn output_bit<R: F2LinearRng>(state: u128, bit: u32) -> u128 {
+    let mut rng = R::from_state(state);
+    (rng.next_word() >> bit) as u128 & 1
+}
+
+/// If bit `bit` of the output is a linear function of the state, the vector
+/// `f` such that the bit is the parity of `f & state`.
+pub fn output_functional<R: F2LinearRng>(bit: u32) -> Option<u128> {
+    let mut f = 0;
+    for j in 0..R::STATE_BITS {
+        f |= output_bit::<R>(1 << j, bit) << j;
+    }
+    let states = test_states(R::STATE_BITS, 100);
+    let linear = output_bit::<R>(0, bit) == 0 && states.iter().all(|&state| {
+        output_bit::<R>(state, bit) == (f & state).count_ones() as u128 & 1
+    });
+    if linear { Some(f) } else { None }
+}
+
+/// The dimension of equidistribution of the output bits given by
+/// `functionals`: the largest `k` such that `k` successive outputs of these
+/// bits take every value equally often over the full period.
+///
+/// The bits of `k` outputs are the rows `f M^t` for `t < k` applied to the
+/// state, so this is the largest `k` for which these rows are linearly
+/// independent. They are added one by one to a basis in echelon form.
+pub fn equidistribution(matrix: &Matrix, functionals: &[u128]) -> u32 {
+    if functionals.is_empty() {
+        return 0;
+    }
+    let mut basis = [0u128; 128];
+    let mut rows = functionals.to_vec();
+    let mut k = 0;
+    loop {
+        for row in rows.iter_mut() {
+            let mut x = *row;
+            loop {
+                if x == 0 {
+                    return k;
+                }
+                let high = 127 - x.leading_zeros() as usize;
+                if basis[high] == 0 {
+                    basis[high] = x;
+                    break;
+                }
+                x ^= basis[high];
+            }
+            *row = matrix.left_apply(*row);
+        }
+        k += 1;
+    }
+}
+
+/// The equidistribution of the outputs of an `F2LinearRng`.
+#[derive(Clone, Debug)]
+pub struct Equidistribution {
+    /// The number of bits of the state.
+    pub state_bits: u32,
+    /// For every bit of the output word, the dimension of equidistribution
+    /// of that bit alone, or `None` if it is not a linear function of the
+    /// state.
+    pub bits: Vec<Option<u32>>,
+    /// For `v` from 1 up to the number of linear bits, the dimension of
+    /// equidistribution of the `v` most significant linear bits together.
+    /// The best possible is `state_bits / v`.
+    pub resolutions: Vec<u32>,
+}
+
+impl Equidistribution {
+    /// The bits that are weak: they are just the output of an LFSR.
+    pub fn linear_bits(&self) -> Vec<u32> {
+        (0..self.bits.len() as u32).filter(|&b| self.bits[b as usize].is_some())
+                                   .collect()
+    }
+}
+
+/// Compute the equidistribution of every output bit, and of the most
+/// significant linear bits at every resolution.
+pub fn analyze_equidistribution<R: F2LinearRng>() -> Equidistribution {
+    let matrix = transition_matrix::<R>();
+    let functionals: Vec<Option<u128>> =
+        (0..R::WORD_BITS).map(|bit| output_functional::<R>(bit)).collect();
+    let bits = functionals.iter().map(|f| {
+        f.map(|f| equidistribution(&matrix, &[f]))
+    }).collect();
+
+    let linear: Vec<u128> = functionals.iter().rev()
+                                           .filter_map(|f| *f).collect();
+    let mut resolutions = Vec::new();
+    for v in 1..linear.len() + 1 {
+        resolutions.push(equidistribution(&matrix, &linear[..v]));
+    }
+    Equidistribution {
+        state_bits: R::STATE_BITS,
+        bits,
+        resolutions,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Xorshift128_32Rng, Xorshift128_64Rng, Xorshift128PlusRng,
+         XorshiftMt32Rng, XorshiftMt64Rng, Xoroshiro128PlusRng,
+         Xoroshiro64PlusRng, XoroshiroMt64of128Rng, XoroshiroMt32of128Rng};
+    use super::*;
+
+    macro_rules! check_all {
+        ($($name:ident: $ty:ident),*) => {
+            $(
+                let analysis = analyze::<$ty>();
+                assert!(analysis.linear, "{} is not linear", stringify!($name));
+                assert_eq!(analysis.polynomial.degree(), analysis.state_bits,
+                           "{}", stringify!($name));
+                assert_eq!(analysis.primitive, Some(true),
+                           "characteristic polynomial of {} is not primitive",
+                           stringify!($name));
+            )*
+        }
+    }
+
+    #[test]
+    fn test_primitive() {
+        f2_linear_generators!(check_all);
+    }
+
+    fn check_jump_by_steps<R: F2LinearRng>(name: &str) {
+        let state = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210u128
+                    & mask(R::STATE_BITS);
+        for &k in [0u128, 1, 2, 3, 64, 100, 1000].iter() {
+            let mut rng1 = R::from_state(state);
+            let mut rng2 = R::from_state(state);
+            rng1.jump_by(k);
+            for _ in 0..k {
+                rng2.step();
+            }
+            assert_eq!(rng1.state(), rng2.state(), "{} jumped by {}", name, k);
+        }
+    }
+
+    macro_rules! check_all_jump_by {
+        ($($name:ident: $ty:ident),*) => {
+            $( check_jump_by_steps::<$ty>(stringify!($name)); )*
+        }
+    }
+
+    #[test]
+    fn test_jump_by_steps() {
+        f2_linear_generators!(check_all_jump_by);
+    }
+
+    macro_rules! check_jump {
+        ($($ty:ident),*) => {
+            $(
+                let state = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210u128;
+                let mut rng1 = $ty::from_state(state);
+                let mut rng2 = $ty::from_state(state);
+                rng1.jump();
+                rng2.jump_by(1 << 64);
+                assert_eq!(rng1.state(), rng2.state(), "{}", stringify!($ty));
+            )*
+        }
+    }
+
+    #[test]
+    fn test_jump() {
+        check_jump!(Xoroshiro128PlusRng, Xorshift128PlusRng,
+                    XoroshiroMt64of128Rng, XoroshiroMt32of128Rng);
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Linear complexity of the individual output bits of an RNG.
+//!
+//! The sequence of a single output bit is run through Berlekamp-Massey. For a
+//! random sequence of length `n` the linear complexity stays close to `n / 2`
+//! all the way. The low bits of scrambled linear RNGs like xoroshiro128+ are
+//! the output of an LFSR of modest size, and their complexity stops growing
+//! once the sequence is about twice that size.
+
+use rand_core::RngCore;
+use battery::Outcome;
+use registry::{self, Generator};
+use gf2;
+
+/// The linear complexity of one output bit.
+#[derive(Clone, Debug)]
+pub struct BitComplexity {
+    pub bit: u32,
+    /// The linear complexity of the whole sequence.
+    pub complexity: usize,
+    /// The largest deviation of the profile from `n / 2`, over all prefixes.
+    pub max_deviation: usize,
+    /// The linear complexity after every bit of the sequence.
+    pub profile: Vec<usize>,
+    /// The probability that a random sequence of the same length has a linear
+    /// complexity of at most `complexity`.
+    pub p: f64,
+}
+
+impl BitComplexity {
+    /// The p-value as an `Outcome` of the battery, to evaluate it.
+    pub fn outcome(&self) -> Outcome {
+        Outcome { name: format!("bit {}", self.bit), p: self.p }
+    }
+}
+
+/// The probability that a random sequence of `n` bits has a linear complexity
+/// of at most `l`.
+///
+/// There are 2^(2j - 1) sequences with complexity `0 < j <= n / 2`, and
+/// 2^(2(n - j)) with complexity `j > n / 2`.
+pub fn complexity_p(l: usize, n: usize) -> f64 {
+    if 2 * l <= n {
+        (2.0 * 2f64.powi(2 * l as i32 - n as i32) + 2f64.powi(-(n as i32))) / 3.0
+    } else {
+        1.0 - (2f64.powi(n as i32 - 2 * l as i32) - 2f64.powi(-(n as i32))) / 3.0
+    }
+}
+
+/// Compute the linear complexity profile of `bits` of the output words of a
+/// generator, over the first `length` outputs after seeding it with `seed`.
+pub fn linear_complexity(generator: &Generator, bits: &[u32], length: usize,
+                         seed: u64)
+    -> Vec<BitComplexity>
+{
+    let seed_bytes = registry::expand_seed(seed, generator.seed_bytes);
+    let mut rng = (generator.from_seed)(&seed_bytes);
+    let words: Vec<u64> = (0..length).map(|_| {
+        if generator.word_bits == 32 {
+            rng.next_u32() as u64
+        } else {
+            rng.next_u64()
+        }
+    }).collect();
+
+    bits.iter().map(|&bit| {
+        let mut packed = vec![0u64; length.div_ceil(64)];
+        for (i, word) in words.iter().enumerate() {
+            packed[i / 64] |= ((word >> bit) & 1) << (i % 64);
+        }
+        let profile = gf2::linear_complexity_profile(&packed, length);
+        let max_deviation = profile.iter().enumerate().map(|(i, &l)| {
+            let half = i.div_ceil(2);
+            l.abs_diff(half)
+        }).max().unwrap_or(0);
+        let complexity = profile.last().cloned().unwrap_or(0);
+        BitComplexity {
+            bit,
+            complexity,
+            max_deviation,
+            profile,
+            p: complexity_p(complexity, length),
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use battery::Evaluation;
+    use registry;
+    use super::*;
+
+    #[test]
+    fn test_linear_complexity() {
+        // The lowest bit of xoroshiro128+ is the output of an LFSR of 128
+        // bits, the higher bits are not.
+        let generator = registry::find("xoroshiro_128_plus").unwrap();
+        let result = linear_complexity(generator, &[0, 63], 1000, 0);
+        assert_eq!(result[0].bit, 0);
+        assert_eq!(result[0].complexity, 128);
+        assert_eq!(result[0].profile.len(), 1000);
+        assert_eq!(result[0].profile[999], 128);
+        assert_eq!(result[0].outcome().evaluation(), Evaluation::Fail);
+        assert!(result[1].complexity >= 490 && result[1].complexity <= 510);
+        assert!(result[1].outcome().evaluation() != Evaluation::Fail);
+    }
+}
@@ -0,0 +1,14 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tools to verify the theoretical properties of the RNGs.
+
+pub mod avalanche;
+pub mod f2;
+pub mod lincomp;
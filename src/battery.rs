@@ -0,0 +1,1068 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A battery of statistical tests.
+//!
+//! The tests work on a stream of bytes, as produced by `fill_bytes`, and read
+//! it as little-endian `u64` words. Every test keeps running counts, so the
+//! same test can be evaluated after every doubling of the length of the
+//! stream, like PractRand does.
+//!
+//! The tests are:
+//!
+//! - `monobit`: the number of one bits.
+//! - `runs`: the number of runs of equal bits (NIST SP 800-22, 2.3). Skipped
+//!   when the bits are too unbalanced for it to apply.
+//! - `frequency8`: the distribution of bytes.
+//! - `serial16`: the distribution of non-overlapping pairs of bytes.
+//! - `gap`: the gaps between 32-bit words below 2<sup>28</sup> (Knuth).
+//! - `birthday`: Marsaglia's birthday spacings, with 512 birthdays in a year
+//!   of 2<sup>24</sup> days, from the high and the low 24 bits of 32-bit
+//!   words.
+//! - `rank32`, `rank64`: the rank over GF(2) of 32×32 and 64×64 matrices.
+//! - `lincomp`: the linear complexity of the sequence of one bit of every
+//!   word, in blocks of 512 bits (NIST SP 800-22, 2.10).
+//! - `BCFN`: the Hamming weights of blocks of 2<sup>k</sup> bits, classified
+//!   as low, medium or high, and the distribution of overlapping 4-tuples of
+//!   those classes. Inspired by the test of the same name in PractRand.
+//! - `DC6`: the same for overlapping 6-tuples of the classes of the Hamming
+//!   weights of bytes.
+//! - `HWD`: Blackman and Vigna's Hamming-weight dependency test, on the native
+//!   words of an RNG. Not part of the standard battery, because it depends
+//!   on the word size.
+//!
+//! The tests with overlapping tuples use the difference between the
+//! chi-square statistics of the tuples and the tuples one shorter, which is
+//! again chi-square distributed (Marsaglia).
+
+use std::cmp;
+
+use stats;
+use gf2;
+
+/// The p-value of one test.
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub name: String,
+    pub p: f64,
+}
+
+/// How unusual a p-value is, in the terms of PractRand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Evaluation {
+    Normal,
+    Unusual,
+    Suspicious,
+    VerySuspicious,
+    Fail,
+}
+
+impl Outcome {
+    /// Evaluate the p-value. Both tails count: a p-value very close to 1 is
+    /// just as suspicious as one close to 0.
+    pub fn evaluation(&self) -> Evaluation {
+        let q = self.p.min(1.0 - self.p);
+        if q.is_nan() || q < 1e-10 {
+            Evaluation::Fail
+        } else if q < 1e-6 {
+            Evaluation::VerySuspicious
+        } else if q < 1e-4 {
+            Evaluation::Suspicious
+        } else if q < 1e-3 {
+            Evaluation::Unusual
+        } else {
+            Evaluation::Normal
+        }
+    }
+}
+
+impl Evaluation {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Evaluation::Normal => "normal",
+            Evaluation::Unusual => "unusual",
+            Evaluation::Suspicious => "suspicious",
+            Evaluation::VerySuspicious => "very suspicious",
+            Evaluation::Fail => "FAIL",
+        }
+    }
+}
+
+/// A statistical test.
+pub trait Test {
+    /// Process the next words of the stream.
+    fn process(&mut self, words: &[u64]);
+
+    /// Add the outcomes for the data so far to `out`. Tests without enough
+    /// data for a meaningful result add nothing.
+    fn outcomes(&self, out: &mut Vec<Outcome>);
+}
+
+/// A set of tests that process the same stream.
+pub struct Battery {
+    tests: Vec<Box<dyn Test>>,
+    bytes: u64,
+}
+
+impl Default for Battery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Battery {
+    /// The standard battery, with all tests of this module.
+    pub fn new() -> Battery {
+        Battery::with_tests(vec![
+            Box::new(Monobit::new()),
+            Box::new(Runs::new()),
+            Box::new(Frequency8::new()),
+            Box::new(Serial16::new()),
+            Box::new(Gap::new()),
+            Box::new(BirthdaySpacings::new(true)),
+            Box::new(BirthdaySpacings::new(false)),
+            Box::new(MatrixRank::new(32)),
+            Box::new(MatrixRank::new(64)),
+            Box::new(LinearComplexity::new(0)),
+            Box::new(LinearComplexity::new(63)),
+            Box::new(Bcfn::new()),
+            Box::new(Dc6::new()),
+        ])
+    }
+
+    /// A battery with the given tests.
+    pub fn with_tests(tests: Vec<Box<dyn Test>>) -> Battery {
+        Battery { tests, bytes: 0 }
+    }
+
+    /// Process the next words of the stream with all tests.
+    pub fn process(&mut self, words: &[u64]) {
+        for test in self.tests.iter_mut() {
+            test.process(words);
+        }
+        self.bytes += words.len() as u64 * 8;
+    }
+
+    /// Add a test to the battery. It should be added before any data is
+    /// processed.
+    pub fn add(&mut self, test: Box<dyn Test>) {
+        self.tests.push(test);
+    }
+
+    /// The number of bytes processed so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// The outcomes of all tests for the data so far.
+    pub fn outcomes(&self) -> Vec<Outcome> {
+        let mut out = Vec::new();
+        for test in self.tests.iter() {
+            test.outcomes(&mut out);
+        }
+        out
+    }
+
+    /// Process the words produced by `fill` until a test fails, or until
+    /// `max_bytes` are processed. The outcomes are evaluated after 2^20 bytes,
+    /// every time the length doubles, and once more at the end if the last
+    /// length was not already evaluated.
+    ///
+    /// Returns the most extreme of the failing outcomes; `bytes()` is then the
+    /// length at which it failed.
+    ///
+    /// Panics if `max_bytes` is less than 2^20.
+    pub fn run_until_failure<F>(&mut self, max_bytes: u64, mut fill: F)
+        -> Option<Outcome>
+        where F: FnMut(&mut [u64])
+    {
+        assert!(max_bytes >= 1 << 20, "max_bytes must be at least 2^20");
+        let mut words = [0u64; 1 << 13];
+        let mut next_report = 1 << 20;
+        while self.bytes < max_bytes {
+            fill(&mut words);
+            self.process(&words);
+            if self.bytes < next_report && self.bytes < max_bytes {
+                continue;
+            }
+            next_report *= 2;
+            let failure = self.worst_failure();
+            if failure.is_some() {
+                return failure;
+            }
+        }
+        None
+    }
+
+    /// The most extreme of the failing outcomes for the data so far.
+    fn worst_failure(&self) -> Option<Outcome> {
+        self.outcomes().into_iter().filter(|outcome| {
+            outcome.evaluation() == Evaluation::Fail
+        }).min_by(|a, b| {
+            let qa = a.p.min(1.0 - a.p);
+            let qb = b.p.min(1.0 - b.p);
+            qa.partial_cmp(&qb).unwrap_or(cmp::Ordering::Equal)
+        })
+    }
+}
+
+fn outcome(name: &str, p: f64) -> Outcome {
+    Outcome { name: name.to_string(), p }
+}
+
+/// The number of one bits.
+pub struct Monobit {
+    ones: u64,
+    bits: u64,
+}
+
+impl Default for Monobit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Monobit {
+    pub fn new() -> Monobit {
+        Monobit { ones: 0, bits: 0 }
+    }
+}
+
+impl Test for Monobit {
+    fn process(&mut self, words: &[u64]) {
+        for w in words {
+            self.ones += w.count_ones() as u64;
+        }
+        self.bits += words.len() as u64 * 64;
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        if self.bits == 0 {
+            return;
+        }
+        let z = (2.0 * self.ones as f64 - self.bits as f64) /
+                (self.bits as f64).sqrt();
+        out.push(outcome("monobit", stats::normal_p(z)));
+    }
+}
+
+/// The number of runs of equal bits.
+pub struct Runs {
+    ones: u64,
+    bits: u64,
+    transitions: u64,
+    prev: u64,
+}
+
+impl Default for Runs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Runs {
+    pub fn new() -> Runs {
+        Runs { ones: 0, bits: 0, transitions: 0, prev: 0 }
+    }
+}
+
+impl Test for Runs {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            let mut t = (w ^ ((w << 1) | self.prev)).count_ones() as u64;
+            if self.bits == 0 {
+                // There is no bit before the first.
+                t -= w & 1;
+            }
+            self.transitions += t;
+            self.ones += w.count_ones() as u64;
+            self.bits += 64;
+            self.prev = w >> 63;
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        if self.bits == 0 {
+            return;
+        }
+        let n = self.bits as f64;
+        let pi = self.ones as f64 / n;
+        // The runs test assumes the frequency test passes. If it does not,
+        // there is no meaningful result; `monobit` reports the failure.
+        if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+            return;
+        }
+        let runs = self.transitions as f64 + 1.0;
+        let x = (runs - 2.0 * n * pi * (1.0 - pi)).abs() /
+                (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi));
+        out.push(outcome("runs", stats::erfc(x)));
+    }
+}
+
+/// The distribution of bytes.
+pub struct Frequency8 {
+    counts: Vec<u64>,
+}
+
+impl Default for Frequency8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Frequency8 {
+    pub fn new() -> Frequency8 {
+        Frequency8 { counts: vec![0; 256] }
+    }
+}
+
+impl Test for Frequency8 {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            for i in 0..8 {
+                self.counts[((w >> (i * 8)) & 0xff) as usize] += 1;
+            }
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        let n: u64 = self.counts.iter().sum();
+        if n < 5 * 256 {
+            return;
+        }
+        let expected = vec![n as f64 / 256.0; 256];
+        let x = stats::chi_square(&self.counts, &expected);
+        out.push(outcome("frequency8", stats::chi_square_p(x, 255.0)));
+    }
+}
+
+/// The distribution of non-overlapping pairs of bytes.
+pub struct Serial16 {
+    counts: Vec<u64>,
+}
+
+impl Default for Serial16 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serial16 {
+    pub fn new() -> Serial16 {
+        Serial16 { counts: vec![0; 1 << 16] }
+    }
+}
+
+impl Test for Serial16 {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            for i in 0..4 {
+                self.counts[((w >> (i * 16)) & 0xffff) as usize] += 1;
+            }
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        let n: u64 = self.counts.iter().sum();
+        if n < 5 << 16 {
+            return;
+        }
+        let expected = vec![n as f64 / 65536.0; 1 << 16];
+        let x = stats::chi_square(&self.counts, &expected);
+        out.push(outcome("serial16", stats::chi_square_p(x, 65535.0)));
+    }
+}
+
+/// The gap test: the number of 32-bit words between two words below
+/// 2<sup>28</sup>.
+pub struct Gap {
+    counts: Vec<u64>,
+    gap: Option<usize>,
+}
+
+const GAP_CATEGORIES: usize = 48;
+
+impl Default for Gap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gap {
+    pub fn new() -> Gap {
+        Gap { counts: vec![0; GAP_CATEGORIES + 1], gap: None }
+    }
+
+    fn push(&mut self, x: u32) {
+        if x >> 28 == 0 {
+            if let Some(gap) = self.gap {
+                self.counts[cmp::min(gap, GAP_CATEGORIES)] += 1;
+            }
+            self.gap = Some(0);
+        } else if let Some(ref mut gap) = self.gap {
+            *gap += 1;
+        }
+    }
+}
+
+impl Test for Gap {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            self.push(w as u32);
+            self.push((w >> 32) as u32);
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        let n: u64 = self.counts.iter().sum();
+        let p: f64 = 1.0 / 16.0;
+        let tail = (1.0 - p).powi(GAP_CATEGORIES as i32);
+        if (n as f64) * tail < 5.0 {
+            return;
+        }
+        let mut expected: Vec<f64> = (0..GAP_CATEGORIES)
+            .map(|r| n as f64 * p * (1.0 - p).powi(r as i32))
+            .collect();
+        expected.push(n as f64 * tail);
+        let x = stats::chi_square(&self.counts, &expected);
+        out.push(outcome("gap", stats::chi_square_p(x, GAP_CATEGORIES as f64)));
+    }
+}
+
+/// Marsaglia's birthday spacings test.
+pub struct BirthdaySpacings {
+    high: bool,
+    days: Vec<u32>,
+    duplicates: u64,
+    samples: u64,
+}
+
+const BIRTHDAYS: usize = 512;
+const DAY_BITS: u32 = 24;
+
+impl BirthdaySpacings {
+    /// Use the high 24 bits of every 32-bit word if `high`, and the low 24
+    /// bits otherwise.
+    pub fn new(high: bool) -> BirthdaySpacings {
+        BirthdaySpacings {
+            high,
+            days: Vec::with_capacity(BIRTHDAYS),
+            duplicates: 0,
+            samples: 0,
+        }
+    }
+
+    fn push(&mut self, x: u32) {
+        let day = if self.high { x >> (32 - DAY_BITS) } else { x & ((1 << DAY_BITS) - 1) };
+        self.days.push(day);
+        if self.days.len() == BIRTHDAYS {
+            self.days.sort_unstable();
+            let mut spacings: Vec<u32> = Vec::with_capacity(BIRTHDAYS);
+            spacings.push(self.days[0]);
+            for i in 1..BIRTHDAYS {
+                spacings.push(self.days[i] - self.days[i - 1]);
+            }
+            spacings.sort_unstable();
+            for i in 1..BIRTHDAYS {
+                if spacings[i] == spacings[i - 1] {
+                    self.duplicates += 1;
+                }
+            }
+            self.samples += 1;
+            self.days.clear();
+        }
+    }
+}
+
+impl Test for BirthdaySpacings {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            self.push(w as u32);
+            self.push((w >> 32) as u32);
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        if self.samples < 100 {
+            return;
+        }
+        // Every sample has a Poisson distributed number of duplicate spacings
+        // with mean m^3 / 4n.
+        let m = BIRTHDAYS as f64;
+        let lambda = m * m * m / (4.0 * (1u64 << DAY_BITS) as f64);
+        let p = stats::poisson_p(self.duplicates, lambda * self.samples as f64);
+        let name = if self.high { "birthday(high)" } else { "birthday(low)" };
+        out.push(outcome(name, p));
+    }
+}
+
+/// The probabilities of the rank of a random `n`×`n` matrix over GF(2) being
+/// `n`, `n - 1`, and lower.
+fn rank_probabilities(n: i32) -> [f64; 3] {
+    let p = |r: i32| {
+        let mut x = 2f64.powi(r * (2 * n - r) - n * n);
+        for i in 0..r {
+            let a = 1.0 - 2f64.powi(i - n);
+            x *= a * a / (1.0 - 2f64.powi(i - r));
+        }
+        x
+    };
+    let full = p(n);
+    let one_less = p(n - 1);
+    [full, one_less, 1.0 - full - one_less]
+}
+
+/// The rank of square matrices over GF(2).
+pub struct MatrixRank {
+    size: usize,
+    rows: Vec<u64>,
+    counts: [u64; 3],
+    probabilities: [f64; 3],
+}
+
+impl MatrixRank {
+    /// Test `size`×`size` matrices, where `size` is 32 or 64.
+    pub fn new(size: usize) -> MatrixRank {
+        assert!(size == 32 || size == 64);
+        MatrixRank {
+            size,
+            rows: Vec::with_capacity(size),
+            counts: [0; 3],
+            probabilities: rank_probabilities(size as i32),
+        }
+    }
+
+    fn push(&mut self, row: u64) {
+        self.rows.push(row);
+        if self.rows.len() == self.size {
+            let rank = gf2::rank(&self.rows);
+            self.counts[cmp::min(self.size - rank, 2)] += 1;
+            self.rows.clear();
+        }
+    }
+}
+
+impl Test for MatrixRank {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            if self.size == 32 {
+                self.push(w & 0xffff_ffff);
+                self.push(w >> 32);
+            } else {
+                self.push(w);
+            }
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        let n: u64 = self.counts.iter().sum();
+        if (n as f64) * self.probabilities[2] < 5.0 {
+            return;
+        }
+        let expected: Vec<f64> = self.probabilities.iter().map(|p| p * n as f64).collect();
+        let x = stats::chi_square(&self.counts, &expected);
+        let name = format!("rank{}", self.size);
+        out.push(outcome(&name, stats::chi_square_p(x, 2.0)));
+    }
+}
+
+/// The linear complexity of the sequence of one bit of every word.
+pub struct LinearComplexity {
+    bit: u32,
+    block: Vec<u64>,
+    len: usize,
+    counts: [u64; 7],
+}
+
+const LINCOMP_BLOCK: usize = 512;
+const LINCOMP_PROBABILITIES: [f64; 7] =
+    [0.010417, 0.03125, 0.125, 0.5, 0.25, 0.0625, 0.020833];
+
+impl LinearComplexity {
+    /// Test bit `bit` of every word.
+    pub fn new(bit: u32) -> LinearComplexity {
+        LinearComplexity {
+            bit,
+            block: vec![0; LINCOMP_BLOCK / 64],
+            len: 0,
+            counts: [0; 7],
+        }
+    }
+}
+
+impl Test for LinearComplexity {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            self.block[self.len / 64] |= ((w >> self.bit) & 1) << (self.len % 64);
+            self.len += 1;
+            if self.len == LINCOMP_BLOCK {
+                let m = LINCOMP_BLOCK as f64;
+                let l = gf2::linear_complexity(&self.block, LINCOMP_BLOCK) as f64;
+                // The expected linear complexity, for even M.
+                let mu = m / 2.0 + 8.0 / 36.0 - (m / 3.0 + 2.0 / 9.0) / 2f64.powf(m);
+                let t = l - mu + 2.0 / 9.0;
+                let category = if t <= -2.5 { 0 }
+                               else if t > 2.5 { 6 }
+                               else { (t + 3.5).ceil() as usize - 1 };
+                self.counts[category] += 1;
+                for x in self.block.iter_mut() {
+                    *x = 0;
+                }
+                self.len = 0;
+            }
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        let n: u64 = self.counts.iter().sum();
+        if (n as f64) * LINCOMP_PROBABILITIES[0] < 5.0 {
+            return;
+        }
+        let expected: Vec<f64> = LINCOMP_PROBABILITIES.iter()
+                                                      .map(|p| p * n as f64)
+                                                      .collect();
+        let x = stats::chi_square(&self.counts, &expected);
+        let name = format!("lincomp(bit {})", self.bit);
+        out.push(outcome(&name, stats::chi_square_p(x, 6.0)));
+    }
+}
+
+/// Counts of overlapping tuples of three classes.
+///
+/// A tuple is stored with two bits per class, so that a new class can be
+/// shifted in without a division.
+struct OverlappingTuples {
+    len: u32,
+    probabilities: [f64; 3],
+    counts: Vec<u64>,
+    key: usize,
+    seen: u32,
+}
+
+impl OverlappingTuples {
+    fn new(len: u32, probabilities: [f64; 3]) -> OverlappingTuples {
+        OverlappingTuples {
+            len,
+            probabilities,
+            counts: vec![0; 1 << (2 * len)],
+            key: 0,
+            seen: 0,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, class: usize) {
+        self.key = ((self.key << 2) | class) & (self.counts.len() - 1);
+        if self.seen < self.len {
+            self.seen += 1;
+        }
+        if self.seen == self.len {
+            self.counts[self.key] += 1;
+        }
+    }
+
+    /// The chi-square statistic of the tuples of length `len`, where the
+    /// counts of the shorter tuples are those of their prefixes.
+    fn chi_square(&self, len: u32) -> f64 {
+        let shift = 2 * (self.len - len);
+        let mut counts = vec![0; 1 << (2 * len)];
+        for (key, count) in self.counts.iter().enumerate() {
+            counts[key >> shift] += *count;
+        }
+        let n: u64 = counts.iter().sum();
+        let mut x = 0.0;
+        'keys: for (key, &count) in counts.iter().enumerate() {
+            let mut expected = n as f64;
+            for i in 0..len {
+                let class = (key >> (2 * i)) & 3;
+                if class == 3 {
+                    continue 'keys;
+                }
+                expected *= self.probabilities[class];
+            }
+            let diff = count as f64 - expected;
+            x += diff * diff / expected;
+        }
+        x
+    }
+
+    /// The p-value, or `None` if there are not enough tuples.
+    fn p_value(&self) -> Option<f64> {
+        let n: u64 = self.counts.iter().sum();
+        let min_p = self.probabilities.iter().cloned().fold(1.0, f64::min);
+        if (n as f64) * min_p.powi(self.len as i32) < 5.0 {
+            return None;
+        }
+        let x = self.chi_square(self.len) - self.chi_square(self.len - 1);
+        let df = 2.0 * 3f64.powi(self.len as i32 - 1);
+        Some(stats::chi_square_p(x, df))
+    }
+}
+
+/// Split the possible Hamming weights of `bits` bits into three classes of
+/// roughly equal probability. Returns the highest weight of the low and of the
+/// medium class, and the probabilities of the classes.
+fn weight_classes(bits: u64) -> (u64, u64, [f64; 3]) {
+    let mut cdf = 0.0;
+    let mut bounds = [bits, bits];
+    let mut probabilities = [0.0; 3];
+    for h in 0..bits + 1 {
+        let p = stats::binomial_half_pmf(bits, h);
+        let class = if cdf + p / 2.0 < 1.0 / 3.0 { 0 }
+                    else if cdf + p / 2.0 < 2.0 / 3.0 { 1 }
+                    else { 2 };
+        if class < 2 {
+            bounds[class] = h;
+        }
+        probabilities[class] += p;
+        cdf += p;
+    }
+    (bounds[0], bounds[1], probabilities)
+}
+
+/// One level of the BCFN test: blocks of 2<sup>level</sup> bits.
+struct BcfnLevel {
+    level: u32,
+    low: u64,
+    medium: u64,
+    tuples: OverlappingTuples,
+    /// The weight of the first half of the current block.
+    weight: u64,
+    half: bool,
+}
+
+impl BcfnLevel {
+    fn new(level: u32) -> BcfnLevel {
+        let (low, medium, probabilities) = weight_classes(1 << level);
+        BcfnLevel {
+            level,
+            low,
+            medium,
+            tuples: OverlappingTuples::new(4, probabilities),
+            weight: 0,
+            half: false,
+        }
+    }
+
+    #[inline]
+    fn push_weight(&mut self, weight: u64) {
+        let class = if weight <= self.low { 0 }
+                    else if weight <= self.medium { 1 }
+                    else { 2 };
+        self.tuples.push(class);
+    }
+}
+
+/// The Hamming weights of blocks of bits, for block sizes from 16 bits up to
+/// 16 KiB.
+pub struct Bcfn {
+    levels: Vec<BcfnLevel>,
+}
+
+const BCFN_LEVELS: ::std::ops::Range<u32> = 4..18;
+
+impl Default for Bcfn {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bcfn {
+    pub fn new() -> Bcfn {
+        Bcfn { levels: BCFN_LEVELS.map(BcfnLevel::new).collect() }
+    }
+}
+
+impl Test for Bcfn {
+    fn process(&mut self, words: &[u64]) {
+        let (small, large) = self.levels.split_at_mut(2);
+        for &w in words {
+            for i in 0..4 {
+                small[0].push_weight(((w >> (i * 16)) as u16).count_ones() as u64);
+            }
+            small[1].push_weight((w as u32).count_ones() as u64);
+            small[1].push_weight(((w >> 32) as u32).count_ones() as u64);
+            // The weight of a block at one level is the sum of two blocks of
+            // the level below.
+            let mut weight = w.count_ones() as u64;
+            large[0].push_weight(weight);
+            for level in large[1..].iter_mut() {
+                if !level.half {
+                    level.weight = weight;
+                    level.half = true;
+                    break;
+                }
+                weight += level.weight;
+                level.half = false;
+                level.push_weight(weight);
+            }
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        for level in self.levels.iter() {
+            if let Some(p) = level.tuples.p_value() {
+                out.push(outcome(&format!("BCFN(2^{})", level.level), p));
+            }
+        }
+    }
+}
+
+/// Overlapping 6-tuples of the classes of the Hamming weights of bytes.
+pub struct Dc6 {
+    tuples: OverlappingTuples,
+}
+
+impl Default for Dc6 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dc6 {
+    pub fn new() -> Dc6 {
+        // Weights 0 to 3, 4, and 5 to 8.
+        let p = [93.0 / 256.0, 70.0 / 256.0, 93.0 / 256.0];
+        Dc6 { tuples: OverlappingTuples::new(6, p) }
+    }
+}
+
+impl Test for Dc6 {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            for i in 0..8 {
+                let weight = ((w >> (i * 8)) as u8).count_ones();
+                let class = if weight < 4 { 0 } else if weight == 4 { 1 } else { 2 };
+                self.tuples.push(class);
+            }
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        if let Some(p) = self.tuples.p_value() {
+            out.push(outcome("DC6", p));
+        }
+    }
+}
+
+/// Blackman and Vigna's Hamming-weight dependency test.
+///
+/// The Hamming weight of every word is classified as low, medium or high.
+/// For every signature of `k` successive classes, the test sums the deviation
+/// from the mean of the Hamming weight of the word that follows. Scrambled
+/// linear generators fail because the weights of successive words depend on
+/// each other, even when the weights themselves are fine.
+///
+/// The normalized sums are transformed with an orthonormal transform over
+/// every position of the signature, so that a dependency on a few of the
+/// previous words ends up in a few coefficients. The p-value is that of the
+/// largest coefficient, out of 3<sup>k</sup>.
+///
+/// See "Scrambled Linear Pseudorandom Number Generators", D. Blackman and
+/// S. Vigna, 2018.
+pub struct HammingWeightDependency {
+    word_bits: u32,
+    k: u32,
+    low: u64,
+    medium: u64,
+    /// The current signature, with two bits per class.
+    key: usize,
+    seen: u32,
+    /// The sums of `2 * weight - word_bits` of the next word, by signature.
+    sums: Vec<i64>,
+    counts: Vec<u64>,
+}
+
+impl HammingWeightDependency {
+    /// Test words of `word_bits` bits, 32 or 64, with signatures of `k`
+    /// words. With 32-bit words, every `u64` of the stream is two words,
+    /// low half first.
+    pub fn new(word_bits: u32, k: u32) -> HammingWeightDependency {
+        assert!(word_bits == 32 || word_bits == 64);
+        assert!((1..=12).contains(&k));
+        let (low, medium, _) = weight_classes(word_bits as u64);
+        HammingWeightDependency {
+            word_bits,
+            k,
+            low,
+            medium,
+            key: 0,
+            seen: 0,
+            sums: vec![0; 1 << (2 * k)],
+            counts: vec![0; 1 << (2 * k)],
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, word: u64) {
+        let weight = word.count_ones() as u64;
+        if self.seen == self.k {
+            self.sums[self.key] += 2 * weight as i64 - self.word_bits as i64;
+            self.counts[self.key] += 1;
+        } else {
+            self.seen += 1;
+        }
+        let class = if weight <= self.low { 0 }
+                    else if weight <= self.medium { 1 }
+                    else { 2 };
+        self.key = ((self.key << 2) | class) & (self.sums.len() - 1);
+    }
+}
+
+impl Test for HammingWeightDependency {
+    fn process(&mut self, words: &[u64]) {
+        for &w in words {
+            if self.word_bits == 32 {
+                self.push(w & 0xffff_ffff);
+                self.push(w >> 32);
+            } else {
+                self.push(w);
+            }
+        }
+    }
+
+    fn outcomes(&self, out: &mut Vec<Outcome>) {
+        let signatures = 3usize.pow(self.k);
+        let n: u64 = self.counts.iter().sum();
+        if n < 10 * signatures as u64 {
+            return;
+        }
+        // Every normalized sum is standard normal if the words are
+        // independent, because 2 * weight - word_bits has variance
+        // word_bits.
+        let mut z: Vec<f64> = (0..signatures).map(|index| {
+            let mut key = 0;
+            let mut rest = index;
+            for i in 0..self.k {
+                key |= (rest % 3) << (2 * i);
+                rest /= 3;
+            }
+            let count = self.counts[key];
+            if count == 0 {
+                0.0
+            } else {
+                self.sums[key] as f64 / (count as f64 * self.word_bits as f64).sqrt()
+            }
+        }).collect();
+
+        let (s3, s2, s6) = (3f64.sqrt(), 2f64.sqrt(), 6f64.sqrt());
+        let mut stride = 1;
+        while stride < signatures {
+            let mut block = 0;
+            while block < signatures {
+                for i in block..block + stride {
+                    let (a, b, c) = (z[i], z[i + stride], z[i + 2 * stride]);
+                    z[i] = (a + b + c) / s3;
+                    z[i + stride] = (a - c) / s2;
+                    z[i + 2 * stride] = (a - 2.0 * b + c) / s6;
+                }
+                block += 3 * stride;
+            }
+            stride *= 3;
+        }
+
+        let max = z.iter().fold(0.0f64, |max, x| max.max(x.abs()));
+        // The probability that the largest of `signatures` coefficients is
+        // at least this large.
+        let q = stats::normal_p(max);
+        let p = -((signatures as f64) * (-q).ln_1p()).exp_m1();
+        out.push(outcome(&format!("HWD(k = {})", self.k), p));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use Sfc64Rng;
+    use super::*;
+
+    #[test]
+    fn test_runs_unbalanced() {
+        // Too many ones for the runs test to apply: only `monobit` fails.
+        let mut battery = Battery::with_tests(vec![Box::new(Monobit::new()),
+                                                   Box::new(Runs::new())]);
+        battery.process(&[!0u64; 1 << 10]);
+        let outcomes = battery.outcomes();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].name, "monobit");
+        assert_eq!(outcomes[0].evaluation(), Evaluation::Fail);
+    }
+
+    #[test]
+    fn test_run_until_failure_evaluates_at_end() {
+        // The stream only goes bad after the first evaluation at 2^20 bytes,
+        // and stops before the next one at 2^21.
+        let mut rng = Sfc64Rng::seed_from_u64(0);
+        let mut bytes = 0;
+        let mut battery = Battery::with_tests(vec![Box::new(Monobit::new())]);
+        let failure = battery.run_until_failure(3 << 19, |words| {
+            for w in words.iter_mut() {
+                *w = if bytes < 1 << 20 { rng.next_u64() } else { 0 };
+                bytes += 8;
+            }
+        });
+        assert_eq!(failure.unwrap().name, "monobit");
+        assert_eq!(battery.bytes(), 3 << 19);
+    }
+
+    #[test]
+    fn test_battery_passes_sfc64() {
+        let mut rng = Sfc64Rng::seed_from_u64(0);
+        let mut battery = Battery::new();
+        let failure = battery.run_until_failure(1 << 20, |words| {
+            for w in words.iter_mut() {
+                *w = rng.next_u64();
+            }
+        });
+        assert!(failure.is_none(), "{:?}", failure);
+        assert_eq!(battery.bytes(), 1 << 20);
+    }
+
+    /// Whether `test` fails on `words`.
+    fn fails<T: Test + 'static>(test: T, words: &[u64]) -> bool {
+        let mut battery = Battery::with_tests(vec![Box::new(test)]);
+        battery.process(words);
+        let outcomes = battery.outcomes();
+        assert!(!outcomes.is_empty(), "not enough data");
+        outcomes.iter().any(|outcome| outcome.evaluation() == Evaluation::Fail)
+    }
+
+    /// A counter of 32-bit words, two per `u64`.
+    fn counter(len: usize) -> Vec<u64> {
+        (0..len as u64).map(|i| (2 * i) | (2 * i + 1) << 32).collect()
+    }
+
+    #[test]
+    fn test_defective_streams_fail() {
+        let zeros = vec![0u64; 1 << 16];
+        let halves = vec![0x5555_5555_5555_5555u64; 1 << 16];
+        assert!(fails(Bcfn::new(), &halves));
+        assert!(fails(Dc6::new(), &zeros));
+        assert!(fails(MatrixRank::new(32), &zeros));
+        assert!(fails(MatrixRank::new(64), &zeros));
+        assert!(fails(Gap::new(), &counter(1 << 16)));
+        assert!(fails(BirthdaySpacings::new(true), &zeros));
+        assert!(fails(BirthdaySpacings::new(false), &counter(1 << 16)));
+    }
+
+    #[test]
+    fn test_hamming_weight_dependency() {
+        let mut rng = Sfc64Rng::seed_from_u64(0);
+        let words: Vec<u64> = (0..1 << 16).map(|_| rng.next_u64()).collect();
+        assert!(!fails(HammingWeightDependency::new(64, 3), &words));
+        assert!(!fails(HammingWeightDependency::new(32, 3), &words));
+
+        // Every other word is the complement of the one before it, so its
+        // weight is 64 minus the previous weight.
+        let words: Vec<u64> = (0..1 << 16).map(|i| {
+            if i % 2 == 0 { words[i] } else { !words[i - 1] }
+        }).collect();
+        assert!(fails(HammingWeightDependency::new(64, 3), &words));
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Measure how well the seeding of the RNGs mixes the bits of the seed.
+
+extern crate small_rngs;
+
+use small_rngs::analysis::avalanche;
+use small_rngs::battery::Evaluation;
+use small_rngs::registry::{self, GENERATORS};
+use std::env;
+use std::process;
+
+fn print_usage(cmd: &String) {
+    let mut names: Vec<_> = GENERATORS.iter().map(|g| g.name).collect();
+    names.sort();
+    println!("Usage: {} [RNG..] [--outputs N] [--samples N]
+where RNG is one of: {:?}
+
+Flip every bit of the seed passed to `from_seed`, and measure how many bits of
+the first outputs change. Ideally every output bit changes with a probability
+of one half. For every output the average fraction of changed bits, the
+largest bias of a single pair of seed bit and output bit, and the p-value of a
+chi-square test over all pairs are reported.
+
+Without RNG, test all of them. The seeds are fixed, so the results can be
+compared between versions.
+
+Options:
+    --outputs N    Test the first N outputs (default 4).
+    --samples N    Number of random seeds (default 1000).",
+        cmd, names);
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+fn parse_usize(value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut names = Vec::new();
+    let mut outputs = 4;
+    let mut samples = 1000;
+    let mut i = 1;
+    while i < args.len() {
+        match &*args[i] {
+            opt if opt.starts_with("--") && i + 1 < args.len() => {
+                let value = &args[i + 1];
+                match opt {
+                    "--outputs" => outputs = parse_usize(value),
+                    "--samples" => samples = parse_usize(value),
+                    _ => {
+                        print_usage(&args[0]);
+                        process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            arg if !arg.starts_with("--") => {
+                names.push(arg.to_string());
+                i += 1;
+            }
+            _ => {
+                print_usage(&args[0]);
+                process::exit(1);
+            }
+        }
+    }
+    if outputs == 0 || samples == 0 {
+        fail("--outputs and --samples must be at least 1");
+    }
+
+    let generators: Vec<_> = if names.is_empty() {
+        GENERATORS.iter().collect()
+    } else {
+        names.iter().map(|name| match registry::find(name) {
+            Some(generator) => generator,
+            None => fail(&format!("unknown RNG: {}", name)),
+        }).collect()
+    };
+
+    for generator in generators {
+        let result = avalanche::avalanche(generator, outputs, samples);
+        println!("{} ({}-bit seed, {}-bit words, {} seeds)", generator.name,
+                 result.seed_bits, result.word_bits, result.samples);
+        for (t, output) in result.outputs.iter().enumerate() {
+            let evaluation = output.outcome(t).evaluation();
+            println!("  output {:<3} distance {:.4}  max bias {:.4} \
+                      (seed bit {:>3}, output bit {:>2})  p = {:<11.4e} {}",
+                     t, output.mean_distance, output.max_bias,
+                     output.worst.0, output.worst.1, output.p,
+                     if evaluation == Evaluation::Normal { "" }
+                     else { evaluation.as_str() });
+        }
+    }
+}
@@ -13,77 +13,641 @@
 extern crate rand;
 extern crate small_rngs;
 
-use rand::{Rng, NewSeeded};
-use small_rngs::*;
-use std::collections::HashMap;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use small_rngs::registry::{self, Generator, GENERATORS};
+use small_rngs::streams::{self, Strategy};
+use std::cmp;
 use std::env;
-use std::io::{self, Write, Error};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write, Error, ErrorKind};
 use std::iter::Iterator;
+use std::process;
 
 fn print_usage(cmd: &String, names: Vec<String>) {
-    println!("Usage: {} RNG
-where RNG is one of: {:?}
+    println!("Usage: {0} RNG [OPTIONS]
+       {0} --list [--json]
+       {0} --markdown
+where RNG is one of: {1:?}
 
 This is a small tool to endlessly contatenate output from an RNG. It can for
-example be used with PractRand: ./cat_rng jsf32 | RNG_test stdin -multithreaded",
+example be used with PractRand: ./cat_rng jsf32 | RNG_test stdin -multithreaded
+
+--list prints a table of all RNGs with their properties, or with --json the same
+information as JSON. --markdown prints the table in Markdown, as used in the
+README.
+
+Options:
+    --seed SEED        Seed the RNG with SEED. A SEED starting with 0x is read
+                       as hex bytes, and must be exactly as long as the seed of
+                       the RNG. Otherwise SEED is read as a decimal u64, and
+                       expanded to a full seed with SplitMix64.
+    --seed-file PATH   Seed the RNG with the first bytes of the file at PATH.
+    --bytes N          Stop after N bytes of output from the RNG.
+    --words N          Stop after N words of output from the RNG.
+    --width W          Take W-bit words from the RNG, where W is 8, 16, 32 or
+                       64. Widths up to 32 use `next_u32`, and keep only the
+                       lowest W bits. Without --width the bytes of
+                       `fill_bytes` are used.
+    --bits LO:HI       Only keep bits LO up to (but not including) HI of every
+                       word, with bit 0 the least significant bit. The slices
+                       are packed together without gaps. Requires --width.
+    --reverse-bits     Reverse the order of the kept bits of every word.
+                       Requires --width.
+    --byteswap         Reverse the order of the bytes of every word, before
+                       taking a slice with --bits. Requires --width.
+    --streams K        Combine the output of K instances of the RNG.
+    --mix MODE         How to combine the streams, one of:
+                         interleave  take a word from every instance in turn
+                                     (default)
+                         xor         XOR the words of all instances
+    --stream-seeds S   How to seed the instances, one of:
+                         adjacent  the seed plus 0, 1, .., K-1, read as a
+                                   little-endian integer (default)
+                         splitmix  successive seeds from SplitMix64, started
+                                   from the first 8 bytes of the seed
+                         stream    the same seed, with the stream or key of
+                                   the RNG set to the next K odd constants
+                         jump      the same seed, with every instance jumped
+                                   ahead once more than the previous
+    --format FORMAT    Output format, one of:
+                         raw      raw bytes (default)
+                         raw-be   raw bytes, every word big-endian
+                         hex      one 32-bit word per line in hex
+                         u32      one 32-bit word per line in decimal
+                         u64      one 64-bit word per line in decimal
+                         ascii32  like u32, with the header of the ASCII
+                                  integer format of TestU01 and dieharder.
+                                  Requires --bytes or --words.
+
+Without a seed option the RNG is seeded from the OS. The seed that is used is
+printed to stderr in hex, so that any run can be repeated with --seed.
+
+All formats show the same stream of bytes, read as little-endian words. A word
+of an output format is 64 bits for the u64 format, W bits for the raw-be format
+with --width W, and 32 bits otherwise. The limits of --bytes and --words are in
+bytes and words of the output format.
+
+With --width the streams are combined per word of that width. Without --width
+they are combined per 8 bytes of `fill_bytes` output.",
         cmd, names);
 }
 
-type BR = Box<Rng>;
+type BR = Box<dyn RngCore>;
 
-fn main() {
-    let mut ctors: HashMap<&'static str,
-            Box<Fn() -> Result<BR, ::rand::Error>>> = HashMap::new();
-    ctors.insert("ci", Box::new(|| CiRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("gj", Box::new(|| GjRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("jsf32", Box::new(|| Jsf32Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("jsf64", Box::new(|| Jsf64Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("kiss32", Box::new(|| Kiss32Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("kiss64", Box::new(|| Kiss64Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("msws", Box::new(|| MswsRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("mwp", Box::new(|| MwpRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("pcg_xsh_64_lcg", Box::new(|| PcgXsh64LcgRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("pcg_xsl_64_lcg", Box::new(|| PcgXsl64LcgRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("pcg_xsl_128_mcg", Box::new(|| PcgXsl128McgRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("sapparoth_32", Box::new(|| Sapparot32Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("sapparoth_64", Box::new(|| Sapparot64Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("sfc_32", Box::new(|| Sfc32Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("sfc_64", Box::new(|| Sfc64Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("velox", Box::new(|| Velox3bRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xorshift_128_32", Box::new(|| Xorshift128_32Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xorshift_128_64", Box::new(|| Xorshift128_64Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xorshift_128_plus", Box::new(|| Xorshift128PlusRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xorshift_mt_32", Box::new(|| XorshiftMt32Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xorshift_mt_64", Box::new(|| XorshiftMt64Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xoroshiro_128_plus", Box::new(|| Xoroshiro128PlusRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xoroshiro_64_plus", Box::new(|| Xoroshiro64PlusRng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xoroshiro_mt_64of128", Box::new(|| XoroshiroMt64of128Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xoroshiro_mt_32of128", Box::new(|| XoroshiroMt32of128Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xsm32", Box::new(|| Xsm32Rng::new().map(|rng| Box::new(rng) as BR)));
-    ctors.insert("xsm64", Box::new(|| Xsm64Rng::new().map(|rng| Box::new(rng) as BR)));
+/// Where to get the seed from.
+enum SeedSource {
+    Os,
+    Hex(Vec<u8>),
+    U64(u64),
+    File(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Raw,
+    RawBe,
+    Hex,
+    U32,
+    U64,
+    Ascii32,
+}
+
+impl Format {
+    fn parse(s: &str) -> Option<Format> {
+        match s {
+            "raw" => Some(Format::Raw),
+            "raw-be" => Some(Format::RawBe),
+            "hex" => Some(Format::Hex),
+            "u32" => Some(Format::U32),
+            "u64" => Some(Format::U64),
+            "ascii32" => Some(Format::Ascii32),
+            _ => None,
+        }
+    }
+
+    /// Size in bytes of a word of this format, for words of `width` bits from
+    /// the RNG.
+    fn word_size(&self, width: Option<u32>) -> u64 {
+        match (*self, width) {
+            (Format::U64, _) => 8,
+            (Format::RawBe, Some(width)) => width as u64 / 8,
+            _ => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mix {
+    Interleave,
+    Xor,
+}
+
+/// Produces the bytes to output.
+trait Source {
+    fn fill(&mut self, dest: &mut [u8]);
+}
+
+/// The bytes of `fill_bytes`, combined per 8 bytes if there are multiple
+/// RNGs.
+struct Bytes {
+    rngs: Vec<BR>,
+    mix: Mix,
+    next: usize,
+}
+
+impl Source for Bytes {
+    fn fill(&mut self, dest: &mut [u8]) {
+        if self.rngs.len() == 1 {
+            // Keep the unrolled `fill_bytes` of the generator.
+            self.rngs[0].fill_bytes(dest);
+            return;
+        }
+        match self.mix {
+            Mix::Interleave => {
+                for chunk in dest.chunks_mut(8) {
+                    self.rngs[self.next].fill_bytes(chunk);
+                    self.next = (self.next + 1) % self.rngs.len();
+                }
+            }
+            Mix::Xor => {
+                let mut tmp = [0u8; 8];
+                for chunk in dest.chunks_mut(8) {
+                    self.rngs[0].fill_bytes(chunk);
+                    for rng in self.rngs[1..].iter_mut() {
+                        let tmp = &mut tmp[..chunk.len()];
+                        rng.fill_bytes(tmp);
+                        for (x, y) in chunk.iter_mut().zip(tmp.iter()) {
+                            *x ^= *y;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
 
+/// Bit slices of words of a fixed width, packed into bytes in little-endian
+/// order.
+struct Words {
+    rngs: Vec<BR>,
+    mix: Mix,
+    next: usize,
+    width: u32,
+    byteswap: bool,
+    lo: u32,
+    hi: u32,
+    reverse_bits: bool,
+    // Bits of the current word that are not yet written.
+    word: u64,
+    word_bits: u32,
+}
+
+impl Words {
+    fn next_word(&mut self) -> u64 {
+        let mut x = match self.mix {
+            Mix::Interleave => {
+                let i = self.next;
+                self.next = (i + 1) % self.rngs.len();
+                self.next_raw(i)
+            }
+            Mix::Xor => {
+                (0..self.rngs.len()).fold(0, |acc, i| acc ^ self.next_raw(i))
+            }
+        };
+        if self.byteswap {
+            x = x.swap_bytes() >> (64 - self.width);
+        }
+        x = (x >> self.lo) & mask(self.hi - self.lo);
+        if self.reverse_bits {
+            x = reverse_bits(x, self.hi - self.lo);
+        }
+        x
+    }
+
+    fn next_raw(&mut self, i: usize) -> u64 {
+        match self.width {
+            64 => self.rngs[i].next_u64(),
+            w => (self.rngs[i].next_u32() as u64) & mask(w),
+        }
+    }
+}
+
+impl Source for Words {
+    fn fill(&mut self, dest: &mut [u8]) {
+        let bits = self.hi - self.lo;
+        for byte in dest.iter_mut() {
+            let mut acc = 0u64;
+            let mut acc_bits = 0;
+            while acc_bits < 8 {
+                if self.word_bits == 0 {
+                    self.word = self.next_word();
+                    self.word_bits = bits;
+                }
+                let take = cmp::min(8 - acc_bits, self.word_bits);
+                acc |= (self.word & mask(take)) << acc_bits;
+                self.word >>= take;
+                self.word_bits -= take;
+                acc_bits += take;
+            }
+            *byte = acc as u8;
+        }
+    }
+}
+
+/// A mask of the lowest `bits` bits.
+fn mask(bits: u32) -> u64 {
+    if bits >= 64 { !0 } else { (1 << bits) - 1 }
+}
+
+/// Reverse the order of the lowest `bits` bits of `x`.
+fn reverse_bits(x: u64, bits: u32) -> u64 {
+    let mut r = 0;
+    for i in 0..bits {
+        r |= ((x >> i) & 1) << (bits - 1 - i);
+    }
+    r
+}
+
+/// Length of the output.
+enum Limit {
+    Endless,
+    Bytes(u64),
+    Words(u64),
+}
+
+fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        print_usage(&args[0], ctors.keys().map(|s| String::from(*s)).collect());
-    } else {
-        if let Some(ctor) = ctors.get(&*args[1]) {
-            let rng = ctor().unwrap();
-            cat_rng(rng).unwrap();
+    let names = || sorted_generators().iter().map(|g| String::from(g.name)).collect();
+
+    let mut rng_name = None;
+    let mut seed_source = SeedSource::Os;
+    let mut limit = Limit::Endless;
+    let mut format = Format::Raw;
+    let mut width = None;
+    let mut bits = None;
+    let mut reverse_bits = false;
+    let mut byteswap = false;
+    let mut streams = 1;
+    let mut mix = Mix::Interleave;
+    let mut stream_seeds = Strategy::Adjacent;
+    let mut list = false;
+    let mut json = false;
+    let mut i = 1;
+    while i < args.len() {
+        match &*args[i] {
+            "--list" => {
+                list = true;
+                i += 1;
+            }
+            "--json" => {
+                json = true;
+                i += 1;
+            }
+            "--markdown" => {
+                print!("{}", registry::markdown_table());
+                return;
+            }
+            "--reverse-bits" => {
+                reverse_bits = true;
+                i += 1;
+            }
+            "--byteswap" => {
+                byteswap = true;
+                i += 1;
+            }
+            opt if opt.starts_with("--") && i + 1 < args.len() => {
+                let value = &args[i + 1];
+                match opt {
+                    "--seed-file" => seed_source = SeedSource::File(value.clone()),
+                    "--seed" if value.starts_with("0x") => {
+                        seed_source = match parse_hex(&value[2..]) {
+                            Some(bytes) => SeedSource::Hex(bytes),
+                            None => fail(&format!("invalid hex seed: {}", value)),
+                        };
+                    }
+                    "--seed" => seed_source = SeedSource::U64(parse_u64(value)),
+                    "--bytes" => limit = Limit::Bytes(parse_u64(value)),
+                    "--words" => limit = Limit::Words(parse_u64(value)),
+                    "--width" => {
+                        width = match value.parse::<u32>() {
+                            Ok(w) if w == 8 || w == 16 || w == 32 || w == 64 => Some(w),
+                            _ => fail(&format!("invalid width: {}", value)),
+                        };
+                    }
+                    "--bits" => bits = Some(parse_bits(value)),
+                    "--streams" => {
+                        streams = match value.parse::<u32>() {
+                            Ok(k) if k > 0 => k,
+                            _ => fail(&format!("invalid number of streams: {}", value)),
+                        };
+                    }
+                    "--mix" => {
+                        mix = match &**value {
+                            "interleave" => Mix::Interleave,
+                            "xor" => Mix::Xor,
+                            _ => fail(&format!("unknown mix mode: {}", value)),
+                        };
+                    }
+                    "--stream-seeds" => {
+                        stream_seeds = match Strategy::parse(value) {
+                            Some(strategy) => strategy,
+                            None => fail(&format!("unknown stream seeding: {}", value)),
+                        };
+                    }
+                    "--format" => {
+                        format = match Format::parse(value) {
+                            Some(format) => format,
+                            None => fail(&format!("unknown format: {}", value)),
+                        };
+                    }
+                    _ => {
+                        print_usage(&args[0], names());
+                        process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            arg if rng_name.is_none() && !arg.starts_with("--") => {
+                rng_name = Some(arg.to_string());
+                i += 1;
+            }
+            _ => {
+                print_usage(&args[0], names());
+                process::exit(1);
+            }
+        }
+    }
+
+    let word_size = format.word_size(width);
+    let len = match limit {
+        Limit::Endless => None,
+        Limit::Bytes(n) => {
+            if format != Format::Raw && n % word_size != 0 {
+                fail(&format!("--bytes must be a multiple of {} for this format",
+                              word_size));
+            }
+            Some(n)
+        }
+        Limit::Words(n) => match n.checked_mul(word_size) {
+            Some(n) => Some(n),
+            None => fail("--words is too large"),
+        },
+    };
+    if format == Format::Ascii32 && len.is_none() {
+        fail("the ascii32 format requires --bytes or --words");
+    }
+
+    if json && !list {
+        fail("--json requires --list");
+    }
+    if list {
+        if json {
+            print_json();
         } else {
-            println!("Error: unknown RNG: {}", args[1]);
+            print_list();
+        }
+        return;
+    }
+
+    if width.is_none() && (bits.is_some() || reverse_bits || byteswap) {
+        fail("--bits, --reverse-bits and --byteswap require --width");
+    }
+    let (lo, hi) = bits.unwrap_or((0, width.unwrap_or(0)));
+    if let Some(width) = width {
+        if hi > width {
+            fail(&format!("--bits {}:{} is out of range for a width of {}",
+                          lo, hi, width));
+        }
+    }
+
+    let rng_name = match rng_name {
+        Some(name) => name,
+        None => {
+            print_usage(&args[0], names());
+            process::exit(1);
+        }
+    };
+    let generator = match registry::find(&rng_name) {
+        Some(generator) => generator,
+        None => {
+            println!("Error: unknown RNG: {}", rng_name);
             println!();
-            print_usage(&args[0], ctors.keys().map(|s| String::from(*s)).collect());
+            print_usage(&args[0], names());
+            process::exit(1);
         }
+    };
+
+    let seed = match get_seed(seed_source, generator.seed_bytes) {
+        Ok(seed) => seed,
+        Err(msg) => fail(&msg),
+    };
+    let seed_hex = to_hex(&seed);
+    eprintln!("seed: 0x{}", seed_hex);
+
+    let rngs = match streams::make_rngs(generator, &seed, streams, stream_seeds) {
+        Ok(rngs) => rngs,
+        Err(msg) => fail(&msg),
+    };
+    let mut source: Box<dyn Source> = match width {
+        None => Box::new(Bytes { rngs, mix, next: 0 }),
+        Some(width) => Box::new(Words {
+            rngs,
+            mix,
+            next: 0,
+            width,
+            byteswap,
+            lo,
+            hi,
+            reverse_bits,
+            word: 0,
+            word_bits: 0,
+        }),
+    };
+    if let Err(e) = cat_rng(&mut *source, format, word_size, len, &rng_name, &seed_hex) {
+        // The reader of a pipe going away is the normal way to stop an endless
+        // stream.
+        if e.kind() == ErrorKind::BrokenPipe {
+            process::exit(0);
+        }
+        fail(&e.to_string());
     }
 }
 
-fn cat_rng(mut rng: Box<Rng>) -> Result<(), Error> {
-    let mut buf =  [0u8; 32];
+/// All generators, sorted by name.
+fn sorted_generators() -> Vec<&'static Generator> {
+    let mut generators: Vec<_> = GENERATORS.iter().collect();
+    generators.sort_by_key(|g| g.name);
+    generators
+}
+
+fn print_list() {
+    println!("{:<21} {:<22} {:>5} {:>4} {:>4} {:<10} {:<9} PractRand",
+             "name", "type", "state", "word", "seed", "period",
+             "BigCrush");
+    for g in sorted_generators() {
+        println!("{:<21} {:<22} {:>5} {:>4} {:>4} {:<10} {:<9} {}",
+                 g.name, g.type_name, g.state_bits, g.word_bits,
+                 g.seed_bytes * 8, g.period, g.big_crush.as_str(),
+                 g.practrand.as_str());
+    }
+}
+
+fn print_json() {
+    let entries: Vec<String> = sorted_generators().iter().map(|g| {
+        format!("  {{\"name\": {}, \"type\": {}, \"state_bits\": {}, \
+                 \"word_bits\": {}, \"seed_bits\": {}, \"period\": {}, \
+                 \"quality\": {}, \"big_crush\": {}, \"practrand\": {}, \
+                 \"streams\": {}, \"jump\": {}}}",
+                json_string(g.name), json_string(g.type_name), g.state_bits,
+                g.word_bits, g.seed_bytes * 8, json_string(g.period),
+                json_string(g.quality), json_string(g.big_crush.as_str()),
+                json_string(g.practrand.as_str()), g.stream_offset.is_some(),
+                g.jumped.is_some())
+    }).collect();
+    println!("[\n{}\n]", entries.join(",\n"));
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+fn get_seed(source: SeedSource, seed_size: usize) -> Result<Vec<u8>, String> {
+    let mut seed = vec![0u8; seed_size];
+    match source {
+        SeedSource::Os => {
+            OsRng.try_fill_bytes(&mut seed).map_err(|e| e.to_string())?;
+        }
+        SeedSource::Hex(bytes) => {
+            if bytes.len() != seed_size {
+                return Err(format!("the seed of this RNG is {} bytes, got {}",
+                                   seed_size, bytes.len()));
+            }
+            seed.copy_from_slice(&bytes);
+        }
+        SeedSource::U64(x) => seed = registry::expand_seed(x, seed_size),
+        SeedSource::File(path) => {
+            let mut file = File::open(&path).map_err(|e| format!("{}: {}", path, e))?;
+            file.read_exact(&mut seed).map_err(|e| format!("{}: {}", path, e))?;
+        }
+    }
+    Ok(seed)
+}
+
+/// Parse `LO:HI` with `LO < HI`.
+fn parse_bits(value: &str) -> (u32, u32) {
+    let mut parts = value.splitn(2, ':');
+    let lo = parts.next().and_then(|lo| lo.parse::<u32>().ok());
+    let hi = parts.next().and_then(|hi| hi.parse::<u32>().ok());
+    match (lo, hi) {
+        (Some(lo), Some(hi)) if lo < hi => (lo, hi),
+        _ => fail(&format!("invalid bit range: {}", value)),
+    }
+}
+
+fn parse_u64(value: &str) -> u64 {
+    match value.parse::<u64>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) ||
+       !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    for i in 0..(hex.len() / 2) {
+        match u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16) {
+            Ok(byte) => bytes.push(byte),
+            Err(_) => return None,
+        }
+    }
+    Some(bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write `len` bytes of output of `source` to stdout, or endless output if
+/// `len` is `None`. `len` must be a multiple of `word_size`, the size of a
+/// word of `format`, except for the raw format.
+fn cat_rng(source: &mut dyn Source, format: Format, word_size: u64, len: Option<u64>,
+           name: &str, seed: &str)
+    -> Result<(), Error>
+{
+    let mut buf = [0u8; 4096];
     let stdout = io::stdout();
-    let mut lock = stdout.lock();
+    let mut out = BufWriter::new(stdout.lock());
 
+    if let (Format::Ascii32, Some(len)) = (format, len) {
+        write!(out, "#==================================================================\n\
+                     # generator {}  seed = 0x{}\n\
+                     #==================================================================\n\
+                     type: d\n\
+                     count: {}\n\
+                     numbit: 32\n",
+               name, seed, len / 4)?;
+    }
+
+    let mut remaining = len;
     loop {
-        rng.fill_bytes(&mut buf);
-        lock.write(&buf)?;
+        let n = match remaining {
+            Some(0) => return out.flush(),
+            Some(left) => cmp::min(left, buf.len() as u64) as usize,
+            None => buf.len(),
+        };
+        remaining = remaining.map(|left| left - n as u64);
+
+        let chunk = &mut buf[..n];
+        source.fill(chunk);
+        match format {
+            Format::Raw => out.write_all(chunk)?,
+            Format::RawBe => {
+                for word in chunk.chunks_mut(word_size as usize) {
+                    word.reverse();
+                }
+                out.write_all(chunk)?;
+            }
+            Format::Hex => {
+                for word in chunk.chunks(4) {
+                    writeln!(out, "{:08x}", read_u32_le(word))?;
+                }
+            }
+            Format::U32 | Format::Ascii32 => {
+                for word in chunk.chunks(4) {
+                    writeln!(out, "{}", read_u32_le(word))?;
+                }
+            }
+            Format::U64 => {
+                for word in chunk.chunks(8) {
+                    let x = read_u32_le(&word[..4]) as u64 |
+                            (read_u32_le(&word[4..]) as u64) << 32;
+                    writeln!(out, "{}", x)?;
+                }
+            }
+        }
     }
 }
+
+fn read_u32_le(bytes: &[u8]) -> u32 {
+    bytes[0] as u32 | (bytes[1] as u32) << 8 |
+    (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24
+}
@@ -0,0 +1,279 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Measure the exact cycle lengths of the scaled-down RNGs of
+//! `small_rngs::scaled`, from every seed.
+
+extern crate small_rngs;
+
+use small_rngs::scaled::{ScaledRng, Word, Jsf, Sfc, Xsm, Gj};
+use std::collections::BTreeMap;
+use std::env;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+fn print_usage(cmd: &String) {
+    println!("Usage: {} RNG BITS [--shifts A,B,..] [--seeds N] [--max-steps N]
+          [--threads N] [--exact]
+where RNG is one of: jsf, sfc, xsm, gj
+and BITS is one of: 8, 16, 32, 64
+
+Measure the cycle length and the length of the tail before the cycle from
+every seed of a scaled-down RNG, using Brent's cycle detection algorithm, and
+print the distribution of the cycle lengths.
+
+Options:
+    --shifts A,B,..   Use other shift and rotate constants than the defaults.
+    --seeds N         Only use the seeds 0 to N - 1 (default: all seeds).
+    --max-steps N     Give up on a seed after N steps (default 2^36).
+    --threads N       Number of threads (default 1).
+    --exact           Print every distinct cycle length, not only a histogram
+                      by powers of two.",
+        cmd);
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+fn parse_u32(value: &str) -> u32 {
+    match value.parse::<u32>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+fn parse_u64(value: &str) -> u64 {
+    match value.parse::<u64>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+/// The result of cycle detection: the length of the tail before the cycle,
+/// and the length of the cycle.
+#[derive(Clone, Copy)]
+struct Cycle {
+    tail: u64,
+    length: u64,
+}
+
+/// Brent's cycle detection algorithm. Returns `None` if no cycle is found
+/// within `max_steps` steps.
+fn brent<R: ScaledRng>(start: &R, max_steps: u64) -> Option<Cycle> {
+    // Find the cycle length by moving the tortoise to the hare at every power
+    // of two, until the hare meets it.
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    hare.next_word();
+    let mut steps = 1;
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare.next_word();
+        length += 1;
+        steps += 1;
+        if steps > max_steps {
+            return None;
+        }
+    }
+
+    // Find the start of the cycle with two RNGs `length` steps apart.
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    for _ in 0..length {
+        hare.next_word();
+    }
+    let mut tail = 0;
+    while tortoise != hare {
+        tortoise.next_word();
+        hare.next_word();
+        tail += 1;
+    }
+    Some(Cycle { tail, length })
+}
+
+struct Options {
+    shifts: Option<Vec<u32>>,
+    seeds: Option<u64>,
+    max_steps: u64,
+    threads: usize,
+    exact: bool,
+}
+
+fn run<R: ScaledRng + Send + 'static>(options: &Options) {
+    let shifts = match options.shifts {
+        Some(ref shifts) => shifts.clone(),
+        None => R::default_shifts(),
+    };
+    // Check the constants before starting any threads.
+    if let Err(msg) = R::validate_shifts(&shifts) {
+        fail(&msg);
+    }
+
+    let seed_bits = R::SEED_WORDS * <R::Word as Word>::BITS;
+    let all_seeds = if seed_bits >= 64 { u64::MAX } else { 1 << seed_bits };
+    let seeds = options.seeds.unwrap_or(all_seeds);
+    if seeds > all_seeds {
+        fail(&format!("there are only {} seeds", all_seeds));
+    }
+    println!("{} with {}-bit words, constants {:?}, {} of {} seeds",
+             R::NAME, <R::Word as Word>::BITS, shifts,
+             seeds, all_seeds);
+
+    let start = Instant::now();
+    let next_seed = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..options.threads {
+        let next_seed = next_seed.clone();
+        let tx = tx.clone();
+        let shifts = shifts.clone();
+        let max_steps = options.max_steps;
+        thread::spawn(move || {
+            loop {
+                let seed = next_seed.fetch_add(1, Ordering::Relaxed) as u64;
+                if seed >= seeds {
+                    break;
+                }
+                let rng = R::from_seed(&shifts, seed);
+                tx.send(brent(&rng, max_steps)).unwrap();
+            }
+        });
+    }
+    drop(tx);
+
+    let mut lengths = BTreeMap::new();
+    let mut histogram = BTreeMap::new();
+    let mut unfinished = 0u64;
+    let mut max_tail = 0;
+    for result in rx.iter() {
+        match result {
+            Some(cycle) => {
+                *lengths.entry(cycle.length).or_insert(0u64) += 1;
+                let log2 = 63 - cycle.length.leading_zeros();
+                *histogram.entry(log2).or_insert(0u64) += 1;
+                max_tail = ::std::cmp::max(max_tail, cycle.tail);
+            }
+            None => unfinished += 1,
+        }
+    }
+    let elapsed = start.elapsed();
+
+    println!("{:>14}  {:>12}  {:>9}", "cycle length", "seeds", "fraction");
+    for (log2, count) in histogram.iter() {
+        println!("{:>14}  {:>12}  {:>9.6}", format!("2^{}..2^{}", log2, log2 + 1),
+                 count, *count as f64 / seeds as f64);
+    }
+    if unfinished > 0 {
+        println!("{:>14}  {:>12}  {:>9.6}",
+                 format!(">{}", options.max_steps), unfinished,
+                 unfinished as f64 / seeds as f64);
+    }
+    if options.exact {
+        println!();
+        println!("{:>20}  {:>12}", "exact length", "seeds");
+        for (length, count) in lengths.iter() {
+            println!("{:>20}  {:>12}", length, count);
+        }
+    }
+    println!();
+    if let Some((shortest, _)) = lengths.iter().next() {
+        println!("shortest cycle: {}", shortest);
+    }
+    if let Some((longest, _)) = lengths.iter().next_back() {
+        println!("longest cycle: {}", longest);
+    }
+    println!("longest tail: {}", max_tail);
+    println!("time: {}.{:03} seconds", elapsed.as_secs(),
+             elapsed.subsec_millis());
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut positional = Vec::new();
+    let mut options = Options {
+        shifts: None,
+        seeds: None,
+        max_steps: 1 << 36,
+        threads: 1,
+        exact: false,
+    };
+    let mut i = 1;
+    while i < args.len() {
+        match &*args[i] {
+            "--exact" => {
+                options.exact = true;
+                i += 1;
+            }
+            opt if opt.starts_with("--") && i + 1 < args.len() => {
+                let value = &args[i + 1];
+                match opt {
+                    "--shifts" => {
+                        options.shifts = Some(value.split(',').map(|s| {
+                            parse_u32(s.trim())
+                        }).collect());
+                    }
+                    "--seeds" => options.seeds = Some(parse_u64(value)),
+                    "--max-steps" => options.max_steps = parse_u64(value),
+                    "--threads" => options.threads = parse_u64(value) as usize,
+                    _ => {
+                        print_usage(&args[0]);
+                        process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            arg if !arg.starts_with("--") => {
+                positional.push(arg.to_string());
+                i += 1;
+            }
+            _ => {
+                print_usage(&args[0]);
+                process::exit(1);
+            }
+        }
+    }
+    if positional.len() != 2 {
+        print_usage(&args[0]);
+        process::exit(1);
+    }
+    if options.threads == 0 {
+        fail("--threads must be at least 1");
+    }
+
+    macro_rules! dispatch {
+        ($($name:expr => $rng:ident),*) => {
+            match (&*positional[0], &*positional[1]) {
+                $(
+                    ($name, "8") => run::<$rng<u8>>(&options),
+                    ($name, "16") => run::<$rng<u16>>(&options),
+                    ($name, "32") => run::<$rng<u32>>(&options),
+                    ($name, "64") => run::<$rng<u64>>(&options),
+                )*
+                _ => {
+                    print_usage(&args[0]);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+    dispatch!("jsf" => Jsf, "sfc" => Sfc, "xsm" => Xsm, "gj" => Gj);
+}
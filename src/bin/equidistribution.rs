@@ -0,0 +1,129 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Report the equidistribution of the output bits of the RNGs that are linear
+//! over GF(2).
+
+extern crate small_rngs;
+
+use small_rngs::*;
+use small_rngs::analysis::f2::{self, F2LinearRng};
+use std::env;
+use std::process;
+
+fn print_usage(cmd: &String, names: &[&str]) {
+    println!("Usage: {} [RNG..] [--bits]
+where RNG is one of: {:?}
+
+For every output bit that is a linear function of the state, compute the
+dimension of equidistribution: the number of successive outputs of that bit
+that take every value equally often. Bits that are linear are weak, because
+they are just the output of an LFSR. If the linear bits include the most
+significant bit, also compute the equidistribution of the v most significant
+bits together, and compare it with the best possible of state bits / v.
+
+Without RNG, report on all of them.
+
+Options:
+    --bits    Show every bit, not just a summary of the linear bits.",
+        cmd, names);
+}
+
+/// Format a list of bit positions as ranges, like "0-3, 7".
+fn format_bits(bits: &[u32]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < bits.len() {
+        let mut j = i;
+        while j + 1 < bits.len() && bits[j + 1] == bits[j] + 1 {
+            j += 1;
+        }
+        if i == j {
+            ranges.push(format!("{}", bits[i]));
+        } else {
+            ranges.push(format!("{}-{}", bits[i], bits[j]));
+        }
+        i = j + 1;
+    }
+    if ranges.is_empty() { "none".to_string() } else { ranges.join(", ") }
+}
+
+fn report<R: F2LinearRng>(name: &str, show_bits: bool) {
+    let result = f2::analyze_equidistribution::<R>();
+    let linear = result.linear_bits();
+    println!("{} ({} bits of state, {}-bit words)", name, result.state_bits,
+             R::WORD_BITS);
+    println!("  linear bits: {}", format_bits(&linear));
+
+    if show_bits {
+        for (bit, dimension) in result.bits.iter().enumerate() {
+            match *dimension {
+                Some(k) => println!("  bit {:>2}: linear, {}-dimensionally \
+                                     equidistributed", bit, k),
+                None => println!("  bit {:>2}: not linear", bit),
+            }
+        }
+    } else if !linear.is_empty() {
+        let dimensions: Vec<u32> =
+            linear.iter().map(|&b| result.bits[b as usize].unwrap()).collect();
+        let min = dimensions.iter().cloned().min().unwrap();
+        let max = dimensions.iter().cloned().max().unwrap();
+        println!("  single-bit equidistribution of the linear bits: {}..{}",
+                 min, max);
+    }
+
+    let msb_linear = linear.last() == Some(&(R::WORD_BITS - 1));
+    if msb_linear && result.resolutions.len() > 1 {
+        println!("  {:>4} {:>6} {:>6} {:>8}", "v", "k(v)", "best", "deficit");
+        let mut total = 0;
+        for (i, &k) in result.resolutions.iter().enumerate() {
+            let v = i as u32 + 1;
+            let best = result.state_bits / v;
+            total += best - k;
+            println!("  {:>4} {:>6} {:>6} {:>8}", v, k, best, best - k);
+        }
+        println!("  total deficit: {}", total);
+    }
+    println!();
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    macro_rules! names {
+        ($($name:ident: $ty:ident),*) => { [$(stringify!($name)),*] }
+    }
+    let all_names = f2_linear_generators!(names);
+
+    let mut show_bits = false;
+    let mut names = Vec::new();
+    for arg in args.iter().skip(1) {
+        match &**arg {
+            "--bits" => show_bits = true,
+            name if all_names.contains(&name) => names.push(name.to_string()),
+            _ => {
+                print_usage(&args[0], &all_names);
+                process::exit(1);
+            }
+        }
+    }
+
+    macro_rules! report_all {
+        ($($name:ident: $ty:ident),*) => {
+            $(
+                let name = stringify!($name);
+                if names.is_empty() || names.iter().any(|n| n == name) {
+                    report::<$ty>(name, show_bits);
+                }
+            )*
+        }
+    }
+    f2_linear_generators!(report_all);
+}
@@ -0,0 +1,133 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Report the linear complexity of the individual output bits of an RNG.
+
+extern crate small_rngs;
+
+use small_rngs::analysis::lincomp;
+use small_rngs::battery::Evaluation;
+use small_rngs::registry::{self, GENERATORS};
+use std::env;
+use std::process;
+
+fn print_usage(cmd: &String) {
+    let mut names: Vec<_> = GENERATORS.iter().map(|g| g.name).collect();
+    names.sort();
+    println!("Usage: {} RNG [--bits LIST] [--length N] [--seed SEED] [--profile N]
+where RNG is one of: {:?}
+
+Run Berlekamp-Massey on the sequence of single output bits, and report the
+linear complexity, the largest deviation of the complexity profile from n / 2,
+and the probability that a random sequence has a complexity this low. The low
+bits of scrambled linear RNGs have a complexity far below n / 2, as soon as n
+is more than twice the size of the LFSR behind them.
+
+Options:
+    --bits LIST    Comma-separated list of bits, or `all` (default).
+    --length N     Number of outputs (default 20000).
+    --seed SEED    Seed the RNG with SEED, a decimal u64 which is expanded to
+                   a full seed with SplitMix64 (default 0).
+    --profile N    Also print the complexity profile every N outputs.",
+        cmd, names);
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+fn parse_u64(value: &str) -> u64 {
+    match value.parse::<u64>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut name = None;
+    let mut bits = None;
+    let mut length = 20000;
+    let mut seed = 0;
+    let mut profile_step = 0;
+    let mut i = 1;
+    while i < args.len() {
+        match &*args[i] {
+            opt if opt.starts_with("--") && i + 1 < args.len() => {
+                let value = &args[i + 1];
+                match opt {
+                    "--bits" if value == "all" => bits = None,
+                    "--bits" => {
+                        bits = Some(value.split(',')
+                                         .map(|b| parse_u64(b) as u32)
+                                         .collect::<Vec<_>>());
+                    }
+                    "--length" => length = parse_u64(value) as usize,
+                    "--seed" => seed = parse_u64(value),
+                    "--profile" => profile_step = parse_u64(value) as usize,
+                    _ => {
+                        print_usage(&args[0]);
+                        process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            arg if !arg.starts_with("--") && name.is_none() => {
+                name = Some(arg.to_string());
+                i += 1;
+            }
+            _ => {
+                print_usage(&args[0]);
+                process::exit(1);
+            }
+        }
+    }
+
+    let name = match name {
+        Some(name) => name,
+        None => {
+            print_usage(&args[0]);
+            process::exit(1);
+        }
+    };
+    let generator = match registry::find(&name) {
+        Some(generator) => generator,
+        None => fail(&format!("unknown RNG: {}", name)),
+    };
+    let bits = bits.unwrap_or_else(|| (0..generator.word_bits).collect());
+    if let Some(&bit) = bits.iter().find(|&&b| b >= generator.word_bits) {
+        fail(&format!("{} has {}-bit words, no bit {}",
+                      generator.name, generator.word_bits, bit));
+    }
+    if length == 0 {
+        fail("--length must be at least 1");
+    }
+
+    println!("{} ({}-bit words), {} outputs, seed = {}",
+             generator.name, generator.word_bits, length, seed);
+    println!("  {:>3} {:>10} {:>10}   p", "bit", "complexity", "deviation");
+    let results = lincomp::linear_complexity(generator, &bits, length, seed);
+    for result in results.iter() {
+        let evaluation = result.outcome().evaluation();
+        println!("  {:>3} {:>10} {:>10}   {:<11.4e} {}",
+                 result.bit, result.complexity, result.max_deviation, result.p,
+                 if evaluation == Evaluation::Normal { "" }
+                 else { evaluation.as_str() });
+        if profile_step > 0 {
+            let mut n = profile_step;
+            while n <= length {
+                println!("      n = {:>8}  L = {}", n, result.profile[n - 1]);
+                n += profile_step;
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Verify the periods of the RNGs that are linear over GF(2).
+
+extern crate small_rngs;
+
+use small_rngs::*;
+use small_rngs::analysis::f2::{self, F2LinearRng, Analysis};
+use small_rngs::registry;
+use std::env;
+use std::process;
+
+fn print_usage(cmd: &String) {
+    println!("Usage: {} [--poly]
+
+Build the transition matrix of every xorshift and xoroshiro RNG, compute its
+characteristic polynomial, and test it for primitivity. This proves whether
+the period is 2^n - 1, which is compared with the period in the registry.
+
+Options:
+    --poly    Print the characteristic polynomials.
+
+The exit code is 1 if a period does not match the registry.",
+        cmd);
+}
+
+fn check<R: F2LinearRng>(name: &str, print_poly: bool) -> bool {
+    let analysis: Analysis = f2::analyze::<R>();
+    let maximal = analysis.maximal_period();
+    let result = match maximal {
+        Some(true) => "maximal".to_string(),
+        Some(false) => "NOT maximal".to_string(),
+        None if !analysis.linear => "not linear".to_string(),
+        None => "unknown".to_string(),
+    };
+
+    let claimed = match registry::find(name) {
+        Some(generator) => generator.period,
+        None => "unknown",
+    };
+    let claims_maximal = claimed == format!("2^{} - 1", analysis.state_bits);
+    let ok = maximal == Some(claims_maximal);
+
+    println!("{:<22} {:>5} {:>7} {:<12} {:<12} {}",
+             name, analysis.state_bits, analysis.polynomial.weight(), result,
+             claimed, if ok { "ok" } else { "MISMATCH" });
+    if print_poly {
+        println!("    {}", analysis.polynomial);
+    }
+    ok
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut print_poly = false;
+    for arg in args.iter().skip(1) {
+        match &**arg {
+            "--poly" => print_poly = true,
+            _ => {
+                print_usage(&args[0]);
+                process::exit(1);
+            }
+        }
+    }
+
+    println!("{:<22} {:>5} {:>7} {:<12} {:<12}",
+             "RNG", "state", "weight", "period", "registry");
+    macro_rules! check_all {
+        ($($name:ident: $ty:ident),*) => {
+            [$(check::<$ty>(stringify!($name), print_poly)),*]
+        }
+    }
+    let results = f2_linear_generators!(check_all);
+    if results.iter().any(|&ok| !ok) {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Run the statistical tests of `small_rngs::battery` on an RNG.
+
+extern crate rand;
+extern crate small_rngs;
+
+use rand::RngCore;
+use rand::rngs::OsRng;
+use small_rngs::battery::{Battery, Evaluation, HammingWeightDependency};
+use small_rngs::registry::{self, GENERATORS};
+use std::cmp;
+use std::env;
+use std::process;
+use std::time::Instant;
+
+fn print_usage(cmd: &String) {
+    let mut names: Vec<_> = GENERATORS.iter().map(|g| g.name).collect();
+    names.sort();
+    println!("Usage: {} RNG [--seed SEED] [--min-bytes N] [--max-bytes N] [--all]
+where RNG is one of: {:?}
+
+Run a battery of statistical tests on the output of an RNG, like PractRand
+does, with a Hamming-weight dependency test on its native words added. The
+tests are evaluated after 2^20 bytes, and again every time the length
+doubles, until the RNG fails or the maximum length is reached. If the maximum
+length is not a power of two, they are evaluated once more at the end.
+
+Options:
+    --seed SEED       Seed the RNG with SEED, a decimal u64 which is expanded
+                      to a full seed with SplitMix64. Without it the RNG is
+                      seeded from the OS.
+    --min-bytes N     First length to evaluate the tests at (default 2^20).
+    --max-bytes N     Stop after this many bytes (default 2^30).
+    --all             Show all results, not only the unusual ones.
+
+The exit code is 1 if a test fails.",
+        cmd, names);
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+fn parse_u64(value: &str) -> u64 {
+    match value.parse::<u64>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+/// Format a number of bytes as a power of two, like "64 MiB (2^26 bytes)".
+/// Other lengths are printed as a plain number of bytes.
+fn format_length(bytes: u64) -> String {
+    if !bytes.is_power_of_two() {
+        return format!("{} bytes", bytes);
+    }
+    let log2 = 63 - bytes.leading_zeros();
+    let units = ["bytes", "KiB", "MiB", "GiB", "TiB"];
+    let unit = cmp::min(log2 / 10, units.len() as u32 - 1);
+    format!("{} {} (2^{} bytes)", bytes >> (unit * 10), units[unit as usize], log2)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut rng_name = None;
+    let mut seed = None;
+    let mut min_bytes = 1 << 20;
+    let mut max_bytes = 1 << 30;
+    let mut all = false;
+    let mut i = 1;
+    while i < args.len() {
+        match &*args[i] {
+            "--all" => {
+                all = true;
+                i += 1;
+            }
+            opt if opt.starts_with("--") && i + 1 < args.len() => {
+                let value = &args[i + 1];
+                match opt {
+                    "--seed" => seed = Some(parse_u64(value)),
+                    "--min-bytes" => min_bytes = parse_u64(value),
+                    "--max-bytes" => max_bytes = parse_u64(value),
+                    _ => {
+                        print_usage(&args[0]);
+                        process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            arg if rng_name.is_none() && !arg.starts_with("--") => {
+                rng_name = Some(arg.to_string());
+                i += 1;
+            }
+            _ => {
+                print_usage(&args[0]);
+                process::exit(1);
+            }
+        }
+    }
+
+    let rng_name = match rng_name {
+        Some(name) => name,
+        None => {
+            print_usage(&args[0]);
+            process::exit(1);
+        }
+    };
+    let generator = match registry::find(&rng_name) {
+        Some(generator) => generator,
+        None => fail(&format!("unknown RNG: {}", rng_name)),
+    };
+    if !min_bytes.is_power_of_two() || min_bytes < 1 << 16 {
+        fail("--min-bytes must be a power of two of at least 2^16");
+    }
+    if max_bytes < min_bytes {
+        fail("--max-bytes must be at least --min-bytes");
+    }
+
+    let seed = match seed {
+        Some(seed) => seed,
+        None => {
+            let mut bytes = [0u8; 8];
+            if let Err(e) = OsRng.try_fill_bytes(&mut bytes) {
+                fail(&e.to_string());
+            }
+            bytes.iter().rev().fold(0, |acc, &b| acc << 8 | b as u64)
+        }
+    };
+    let seed_bytes = registry::expand_seed(seed, generator.seed_bytes);
+    let mut rng = (generator.from_seed)(&seed_bytes);
+    println!("rng_test using {} ({}), seed = {}", generator.name,
+             generator.type_name, seed);
+
+    let start = Instant::now();
+    let mut battery = Battery::new();
+    battery.add(Box::new(HammingWeightDependency::new(generator.word_bits, 8)));
+    let mut bytes = [0u8; 1 << 16];
+    let mut words = [0u64; 1 << 13];
+    let mut next_report = min_bytes;
+    let mut failed = false;
+    while battery.bytes() < max_bytes && !failed {
+        rng.fill_bytes(&mut bytes);
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(8)) {
+            *word = chunk.iter().rev().fold(0, |acc, &b| acc << 8 | b as u64);
+        }
+        battery.process(&words);
+        // Evaluate at every power of two, and once more at the end.
+        if battery.bytes() < next_report && battery.bytes() < max_bytes {
+            continue;
+        }
+        next_report *= 2;
+
+        let elapsed = start.elapsed();
+        println!("length = {}, time = {}.{:03} seconds",
+                 format_length(battery.bytes()), elapsed.as_secs(),
+                 elapsed.subsec_millis());
+        let outcomes = battery.outcomes();
+        let mut normal = 0;
+        for outcome in outcomes.iter() {
+            let evaluation = outcome.evaluation();
+            if evaluation == Evaluation::Fail {
+                failed = true;
+            }
+            if evaluation == Evaluation::Normal && !all {
+                normal += 1;
+                continue;
+            }
+            println!("  {:<20} p = {:<12.4e} {}", outcome.name, outcome.p,
+                     evaluation.as_str());
+        }
+        if normal == outcomes.len() {
+            println!("  no anomalies in {} test results", normal);
+        } else if normal > 0 {
+            println!("  ...and {} test results without anomalies", normal);
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
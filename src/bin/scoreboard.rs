@@ -0,0 +1,252 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Measure the speed and run the battery for every generator, and print the
+//! results as the table of the README.
+
+extern crate rand_core;
+extern crate small_rngs;
+
+use rand_core::{RngCore, SeedableRng};
+use small_rngs::*;
+use small_rngs::battery::{Battery, HammingWeightDependency, Outcome};
+use small_rngs::registry::{self, Generator, GENERATORS};
+use std::env;
+use std::hint;
+use std::process;
+use std::time::Instant;
+
+fn print_usage(cmd: &String) {
+    let mut names: Vec<_> = GENERATORS.iter().map(|g| g.name).collect();
+    names.sort();
+    println!("Usage: {} [RNG..] [--format FORMAT] [--max-bytes N] [--seed SEED]
+where RNG is one of: {:?}
+
+Measure the throughput of `next_u32`, `next_u64` and `fill_bytes` of every
+RNG, and run the battery of rng_test on the output of `fill_bytes` until the
+first failure. Print a table with the speed, state size, period and the
+length at which the RNG first fails.
+
+Without RNG, include all of them. Progress is reported on stderr.
+
+Options:
+    --format FORMAT  `markdown` (default) or `csv`.
+    --max-bytes N    Run the battery up to N bytes, a power of two
+                     (default 2^28).
+    --seed SEED      Seed the RNGs with SEED, a decimal u64 which is expanded
+                     to a full seed with SplitMix64 (default 0).",
+        cmd, names);
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+fn parse_u64(value: &str) -> u64 {
+    match value.parse::<u64>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+/// Number of bytes generated for every speed measurement.
+const SPEED_BYTES: usize = 1 << 24;
+
+/// Throughput in MB/s.
+struct Speed {
+    u32: f64,
+    u64: f64,
+    fill: f64,
+}
+
+/// The best throughput of three runs of `f`, which generates `SPEED_BYTES`.
+fn throughput<F: FnMut()>(mut f: F) -> f64 {
+    let mut best = 0.0;
+    for _ in 0..3 {
+        let start = Instant::now();
+        f();
+        let elapsed = start.elapsed();
+        let seconds = elapsed.as_secs() as f64 +
+                      elapsed.subsec_nanos() as f64 * 1e-9;
+        let speed = SPEED_BYTES as f64 / seconds / 1e6;
+        if speed > best {
+            best = speed;
+        }
+    }
+    best
+}
+
+fn speed<R: SeedableRng + RngCore>(seed: &[u8]) -> Speed {
+    let mut rng_seed = R::Seed::default();
+    rng_seed.as_mut().copy_from_slice(seed);
+    let mut rng = R::from_seed(rng_seed);
+    let u32 = throughput(|| {
+        let mut acc = 0;
+        for _ in 0..SPEED_BYTES / 4 {
+            acc ^= rng.next_u32();
+        }
+        hint::black_box(acc);
+    });
+    let u64 = throughput(|| {
+        let mut acc = 0;
+        for _ in 0..SPEED_BYTES / 8 {
+            acc ^= rng.next_u64();
+        }
+        hint::black_box(acc);
+    });
+    let mut buf = vec![0u8; 1 << 16];
+    let fill = throughput(|| {
+        for _ in 0..SPEED_BYTES / buf.len() {
+            rng.fill_bytes(&mut buf);
+        }
+        hint::black_box(&buf);
+    });
+    Speed { u32, u64, fill }
+}
+
+// Measure the speed on the concrete types, not through `Box<dyn RngCore>`.
+macro_rules! measure_speed {
+    ($($name:ident: $ty:ident $props:tt),* $(,)*) => {
+        fn measure_speed(name: &str, seed: &[u8]) -> Speed {
+            match name {
+                $( stringify!($name) => speed::<$ty>(seed), )*
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+generators!(measure_speed);
+
+/// Run the battery on the output of `fill_bytes`, like rng_test, and return
+/// the first failing test and the length at which it failed.
+fn first_failure(generator: &Generator, seed: &[u8], max_bytes: u64)
+    -> Option<(Outcome, u64)>
+{
+    let mut rng = (generator.from_seed)(seed);
+    let mut bytes = vec![0u8; 1 << 16];
+    let mut battery = Battery::new();
+    battery.add(Box::new(HammingWeightDependency::new(generator.word_bits, 8)));
+    battery.run_until_failure(max_bytes, |words| {
+        let bytes = &mut bytes[..words.len() * 8];
+        rng.fill_bytes(bytes);
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(8)) {
+            *word = chunk.iter().rev().fold(0, |acc, &b| acc << 8 | b as u64);
+        }
+    }).map(|outcome| (outcome, battery.bytes()))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Markdown,
+    Csv,
+}
+
+fn log2(x: u64) -> u32 {
+    63 - x.leading_zeros()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut names = Vec::new();
+    let mut format = Format::Markdown;
+    let mut max_bytes = 1 << 28;
+    let mut seed = 0;
+    let mut i = 1;
+    while i < args.len() {
+        match &*args[i] {
+            opt if opt.starts_with("--") && i + 1 < args.len() => {
+                let value = &args[i + 1];
+                match opt {
+                    "--format" => {
+                        format = match &**value {
+                            "markdown" => Format::Markdown,
+                            "csv" => Format::Csv,
+                            _ => fail(&format!("unknown format: {}", value)),
+                        };
+                    }
+                    "--max-bytes" => max_bytes = parse_u64(value),
+                    "--seed" => seed = parse_u64(value),
+                    _ => {
+                        print_usage(&args[0]);
+                        process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            arg if !arg.starts_with("--") => {
+                names.push(arg.to_string());
+                i += 1;
+            }
+            _ => {
+                print_usage(&args[0]);
+                process::exit(1);
+            }
+        }
+    }
+    if !max_bytes.is_power_of_two() || max_bytes < 1 << 20 {
+        fail("--max-bytes must be a power of two of at least 2^20");
+    }
+
+    let mut generators: Vec<_> = if names.is_empty() {
+        GENERATORS.iter().collect()
+    } else {
+        names.iter().map(|name| match registry::find(name) {
+            Some(generator) => generator,
+            None => fail(&format!("unknown RNG: {}", name)),
+        }).collect()
+    };
+    generators.sort_by_key(|g| g.name);
+
+    match format {
+        Format::Markdown => {
+            println!("| Name | Word size | State | Period | next_u32 (MB/s) | \
+                      next_u64 (MB/s) | fill_bytes (MB/s) | First failure |");
+            println!("|------|----------:|------:|--------|----------------:|\
+                      ----------------:|------------------:|---------------|");
+        }
+        Format::Csv => {
+            println!("name,word_bits,state_bits,period,next_u32_mb_s,\
+                      next_u64_mb_s,fill_bytes_mb_s,failure_bytes,failure_test");
+        }
+    }
+    for generator in generators {
+        eprintln!("{}...", generator.name);
+        let seed = registry::expand_seed(seed, generator.seed_bytes);
+        let speed = measure_speed(generator.name, &seed);
+        let failure = first_failure(generator, &seed, max_bytes);
+        match format {
+            Format::Markdown => {
+                let failure = match failure {
+                    Some((outcome, bytes)) => {
+                        format!("2^{} bytes ({})", log2(bytes), outcome.name)
+                    }
+                    None => format!("none up to 2^{} bytes", log2(max_bytes)),
+                };
+                println!("| `{}` | {} | {} | {} | {:.0} | {:.0} | {:.0} | {} |",
+                         generator.name, generator.word_bits,
+                         generator.state_bits, generator.period, speed.u32,
+                         speed.u64, speed.fill, failure);
+            }
+            Format::Csv => {
+                let (bytes, test) = match failure {
+                    Some((outcome, bytes)) => (bytes.to_string(), outcome.name),
+                    None => (String::new(), String::new()),
+                };
+                println!("{},{},{},{},{:.0},{:.0},{:.0},{},{}",
+                         generator.name, generator.word_bits,
+                         generator.state_bits, generator.period, speed.u32,
+                         speed.u64, speed.fill, bytes, test);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Test for correlations between the instances of an RNG, with the different
+//! ways to seed them for parallel streams.
+
+extern crate small_rngs;
+
+use small_rngs::battery::{Battery, HammingWeightDependency, Outcome};
+use small_rngs::registry::{self, Generator, GENERATORS};
+use small_rngs::streams::{self, Interleaved, Strategy};
+use std::env;
+use std::process;
+
+fn print_usage(cmd: &String) {
+    let mut names: Vec<_> = GENERATORS.iter().map(|g| g.name).collect();
+    names.sort();
+    println!("Usage: {} [RNG..] [--strategy S] [--streams K] [--seed SEED]
+        [--max-bytes N]
+where RNG is one of: {:?}
+
+Create K instances of an RNG with a seeding strategy, interleave their words,
+and run the battery of rng_test on the combined stream. The battery is
+evaluated at every doubling of the length, and stops at the first failure.
+The result is a verdict for every RNG and strategy.
+
+Without RNG, test all of them. Strategies that an RNG does not support are
+skipped.
+
+Options:
+    --strategy S     The seeding strategy, or `all` (default). One of:
+                       adjacent  the seed plus 0, 1, .., K-1, read as a
+                                 little-endian integer
+                       splitmix  successive seeds from SplitMix64
+                       stream    the same seed, with the stream or key of the
+                                 RNG set to the next K odd constants
+                       jump      the same seed, with every instance jumped
+                                 ahead once more than the previous
+    --streams K      Number of instances (default 16).
+    --seed SEED      The base seed, a decimal u64 which is expanded to a full
+                     seed with SplitMix64 (default 0).
+    --max-bytes N    Stop after N bytes (default 2^28).
+
+The exit code is 1 if any combination fails.",
+        cmd, names);
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+fn parse_u64(value: &str) -> u64 {
+    match value.parse::<u64>() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("invalid number: {}", value)),
+    }
+}
+
+/// Run the battery on the interleaved instances, and return the failing
+/// test and the length at which it failed.
+fn run(generator: &Generator, strategy: Strategy, streams: u32, seed: u64,
+       max_bytes: u64)
+    -> Option<(Outcome, u64)>
+{
+    let seed = registry::expand_seed(seed, generator.seed_bytes);
+    let rngs = match streams::make_rngs(generator, &seed, streams, strategy) {
+        Ok(rngs) => rngs,
+        Err(msg) => fail(&msg),
+    };
+    let mut source = Interleaved::new(rngs, generator.word_bits);
+
+    let mut battery = Battery::new();
+    battery.add(Box::new(HammingWeightDependency::new(generator.word_bits, 8)));
+    battery.run_until_failure(max_bytes, |words| source.fill(words))
+           .map(|outcome| (outcome, battery.bytes()))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut names = Vec::new();
+    let mut strategies = Strategy::ALL.to_vec();
+    let mut streams = 16;
+    let mut seed = 0;
+    let mut max_bytes = 1 << 28;
+    let mut i = 1;
+    while i < args.len() {
+        match &*args[i] {
+            opt if opt.starts_with("--") && i + 1 < args.len() => {
+                let value = &args[i + 1];
+                match opt {
+                    "--strategy" if value == "all" => {
+                        strategies = Strategy::ALL.to_vec();
+                    }
+                    "--strategy" => {
+                        strategies = match Strategy::parse(value) {
+                            Some(strategy) => vec![strategy],
+                            None => fail(&format!("unknown strategy: {}", value)),
+                        };
+                    }
+                    "--streams" => streams = parse_u64(value) as u32,
+                    "--seed" => seed = parse_u64(value),
+                    "--max-bytes" => max_bytes = parse_u64(value),
+                    _ => {
+                        print_usage(&args[0]);
+                        process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            arg if !arg.starts_with("--") => {
+                names.push(arg.to_string());
+                i += 1;
+            }
+            _ => {
+                print_usage(&args[0]);
+                process::exit(1);
+            }
+        }
+    }
+    if streams < 2 {
+        fail("--streams must be at least 2");
+    }
+
+    let generators: Vec<_> = if names.is_empty() {
+        GENERATORS.iter().collect()
+    } else {
+        names.iter().map(|name| match registry::find(name) {
+            Some(generator) => generator,
+            None => fail(&format!("unknown RNG: {}", name)),
+        }).collect()
+    };
+
+    println!("{} streams, seed = {}, up to 2^{} bytes", streams, seed,
+             63 - max_bytes.leading_zeros());
+    println!("{:<22} {:<10} result", "RNG", "strategy");
+    let mut any_failed = false;
+    for generator in generators {
+        for &strategy in strategies.iter() {
+            if !strategy.supports(generator) {
+                continue;
+            }
+            let result = match run(generator, strategy, streams, seed, max_bytes) {
+                Some((outcome, bytes)) => {
+                    any_failed = true;
+                    format!("FAIL at 2^{} bytes: {} p = {:.4e}",
+                            63 - bytes.leading_zeros(), outcome.name, outcome.p)
+                }
+                None => "pass".to_string(),
+            };
+            println!("{:<22} {:<10} {}", generator.name, strategy.as_str(),
+                     result);
+        }
+    }
+    if any_failed {
+        process::exit(1);
+    }
+}
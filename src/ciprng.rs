@@ -9,7 +9,9 @@
 
 //! CIPRNG v3
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
 use core::fmt;
 
 /// Chaotic Iterations PRNG
@@ -17,10 +19,10 @@ use core::fmt;
 /// - Author: Jacques M. Bahi et al
 /// - License: Public domain (?)
 /// - Source: ["FPGA acceleration of a pseudorandom number generator based on
-///            chaotic iterations"](https://ai2-s2-pdfs.s3.amazonaws.com/7582/3db79773d60451b758bbdb288566ec7f6cbe.pdf).
+///   chaotic iterations"](https://ai2-s2-pdfs.s3.amazonaws.com/7582/3db79773d60451b758bbdb288566ec7f6cbe.pdf).
 /// - Period: N/A / 2^64?
 /// - State: 192 bits
-/// - Word size: 64 bits
+/// - Word size: 32 bits
 /// - Seed size: 192 bits
 #[derive(Clone)]
 pub struct CiRng {
@@ -40,33 +42,42 @@ impl SeedableRng for CiRng {
     type Seed = [u8; 24];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for CiRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 3];
         le::read_u64_into(&seed, &mut seed_u64);
         let mut seed_u32 = [0u32; 6];
         le::read_u32_into(&seed, &mut seed_u32);
 
-        CiRng {
-            t1: if seed_u64[0] != 0 { seed_u64[0] } else { 0x0DD_B1A5E5_BAD_5EED },
-            t2: if seed_u64[1] != 0 { seed_u64[1] } else { 0x0DD_B1A5E5_BAD_5EED },
-            t4: if seed_u32[4] != 0 { seed_u32[4] } else { 0xBAD_5EED },
+        let mut state = CiRng {
+            t1: seed_u64[0],
+            t2: seed_u64[1],
+            t4: seed_u32[4],
             x: seed_u32[5],
+        };
+        if policy.check_bad_state {
+            if state.t1 == 0 { state.t1 = seed_policy::BAD_SEED_REPLACEMENT; }
+            if state.t2 == 0 { state.t2 = seed_policy::BAD_SEED_REPLACEMENT; }
+            if state.t4 == 0 { state.t4 = 0xBAD_5EED; }
         }
-    }
-
-    fn from_rng<R: Rng>(mut rng: R) -> Result<Self, Error> {
-        let mut t1 = 0;
-        while t1 == 0 { t1 = rng.next_u64() }
-        let mut t2 = 0;
-        while t2 == 0 { t2 = rng.next_u64() }
-        let mut t4 = 0;
-        while t4 == 0 { t4 = rng.next_u32() }
-        let x = rng.next_u32();
-
-        Ok(CiRng { t1: t1, t2:t2, t4: t4, x: x })
+        for _ in 0..policy.warm_up {
+            state.next_u32();
+        }
+        state
     }
 }
 
-impl Rng for CiRng {
+impl RngCore for CiRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         // Xorshift 1
@@ -93,11 +104,11 @@ impl Rng for CiRng {
         // if self.t4 & 1 != 0 { self.x ^= self.t1 as u32; }
         // if self.t4 & 2 != 0 { self.x ^= (self.t1 >> 32) as u32; }
         // if self.t4 & 4 != 0 { self.x ^= self.t2 as u32; }
-        let apply = !((self.t4 >> 0) as u32 & 1).wrapping_sub(1);
+        let apply = !(self.t4 & 1).wrapping_sub(1);
         self.x ^= self.t1 as u32 & apply;
-        let apply = !((self.t4 >> 1) as u32 & 1).wrapping_sub(1);
+        let apply = !((self.t4 >> 1) & 1).wrapping_sub(1);
         self.x ^= (self.t1 >> 32) as u32 & apply;
-        let apply = !((self.t4 >> 2) as u32 & 1).wrapping_sub(1);
+        let apply = !((self.t4 >> 2) & 1).wrapping_sub(1);
         self.x ^= self.t2 as u32 & apply;
 
         self.x ^= (self.t2 >> 32) as u32;
@@ -109,16 +120,14 @@ impl Rng for CiRng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest);
+        fill::fill_bytes_via_u32(self, dest);
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
+
+impl FillWords for CiRng {}
@@ -0,0 +1,332 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Jumping ahead in RNGs that are linear over GF(2).
+//!
+//! This is the part of `analysis::f2` that the generators need at run time.
+//! It works on fixed-size arrays only, so `jump_by` is available without the
+//! `std` feature. The parts of `Poly` that only the analysis uses need `std`.
+
+use core::fmt;
+
+/// An RNG with a state transition that is linear over GF(2).
+pub trait F2LinearRng: Sized {
+    /// The number of bits of the state, at most 128.
+    const STATE_BITS: u32;
+
+    /// The number of bits of the native output word, 32 or 64.
+    const WORD_BITS: u32;
+
+    /// The state, packed into the low `STATE_BITS` bits.
+    fn state(&self) -> u128;
+
+    /// Create an RNG with the given state.
+    fn from_state(state: u128) -> Self;
+
+    /// Advance the state by one step, discarding the output.
+    fn step(&mut self);
+
+    /// Advance the state by one step, and return the output word. The output
+    /// does not have to be a linear function of the state.
+    fn next_word(&mut self) -> u64;
+
+    /// Jump ahead by `k` steps.
+    ///
+    /// This computes the characteristic polynomial every time; to make many
+    /// jumps of the same distance, use `jump_polynomial` and `jump_with`
+    /// instead.
+    ///
+    /// Panics if the characteristic polynomial can not be determined, which
+    /// does not happen for an RNG with a maximal period.
+    fn jump_by(&mut self, k: u128) {
+        let jump = jump_polynomial::<Self>(k);
+        jump_with(self, jump);
+    }
+}
+
+/// Mask of the low `bits` bits.
+pub fn mask(bits: u32) -> u128 {
+    if bits >= 128 { !0 } else { (1 << bits) - 1 }
+}
+
+
+/// A monic polynomial over GF(2) of degree at most 128.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Poly {
+    degree: u32,
+    /// The coefficients below the leading term, bit `i` for `x^i`.
+    low: u128,
+}
+
+impl Poly {
+    /// The polynomial `x^degree + low`, where bit `i` of `low` is the
+    /// coefficient of `x^i`.
+    pub fn new(degree: u32, low: u128) -> Poly {
+        assert!(degree <= 128 && low & !mask(degree) == 0);
+        Poly { degree, low }
+    }
+
+    pub fn degree(&self) -> u32 {
+        self.degree
+    }
+
+    /// The coefficients below the leading term.
+    #[cfg(feature = "std")]
+    pub fn low(&self) -> u128 {
+        self.low
+    }
+
+    /// The number of nonzero coefficients. For a good linear generator it
+    /// should be close to half the degree.
+    #[cfg(feature = "std")]
+    pub fn weight(&self) -> u32 {
+        self.low.count_ones() + 1
+    }
+
+    /// `a * b mod self`, for `a` and `b` of lower degree.
+    pub fn mul_mod(&self, a: u128, b: u128) -> u128 {
+        let mut result = 0;
+        for i in (0..self.degree).rev() {
+            result = self.mul_x_mod(result);
+            if (b >> i) & 1 != 0 {
+                result ^= a;
+            }
+        }
+        result
+    }
+
+    /// `a * x mod self`, for `a` of lower degree.
+    pub fn mul_x_mod(&self, a: u128) -> u128 {
+        let carry = (a >> (self.degree - 1)) & 1 != 0;
+        let shifted = (a << 1) & mask(self.degree);
+        if carry { shifted ^ self.low } else { shifted }
+    }
+
+    /// `x^e mod self`.
+    pub fn pow_x_mod(&self, e: u128) -> u128 {
+        let mut result = 1;
+        let mut base = self.mul_x_mod(1);
+        let mut e = e;
+        while e != 0 {
+            if e & 1 != 0 {
+                result = self.mul_mod(result, base);
+            }
+            base = self.mul_mod(base, base);
+            e >>= 1;
+        }
+        result
+    }
+
+    /// Whether the polynomial is primitive, i.e. `x` has order 2^n - 1
+    /// modulo the polynomial. `None` if the factorization of 2^n - 1 is not
+    /// known.
+    #[cfg(feature = "std")]
+    pub fn is_primitive(&self) -> Option<bool> {
+        let factors = mersenne_factors(self.degree)?;
+        let order = mask(self.degree);
+        if self.pow_x_mod(order) != 1 {
+            return Some(false);
+        }
+        Some(factors.iter().all(|&q| self.pow_x_mod(order / q) != 1))
+    }
+}
+
+impl fmt::Display for Poly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "x^{}", self.degree)?;
+        for i in (0..self.degree).rev() {
+            if (self.low >> i) & 1 != 0 {
+                match i {
+                    0 => write!(f, " + 1")?,
+                    1 => write!(f, " + x")?,
+                    _ => write!(f, " + x^{}", i)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The prime factors of 2^n - 1, for the state sizes used by the RNGs.
+#[cfg(feature = "std")]
+pub fn mersenne_factors(n: u32) -> Option<&'static [u128]> {
+    static F32: [u128; 5] = [3, 5, 17, 257, 65537];
+    static F64: [u128; 7] = [3, 5, 17, 257, 641, 65537, 6700417];
+    static F128: [u128; 9] = [3, 5, 17, 257, 641, 65537, 274177, 6700417,
+                             67280421310721];
+    match n {
+        32 => Some(&F32),
+        64 => Some(&F64),
+        128 => Some(&F128),
+        _ => None,
+    }
+}
+
+/// The 64 bits of a packed bit sequence starting at bit `start`, with zeros
+/// past the end.
+#[inline]
+fn word_at(bits: &[u64], start: usize) -> u64 {
+    let q = start / 64;
+    let shift = start % 64;
+    let lo = bits.get(q).cloned().unwrap_or(0);
+    if shift == 0 {
+        lo
+    } else {
+        let hi = bits.get(q + 1).cloned().unwrap_or(0);
+        (lo >> shift) | (hi << (64 - shift))
+    }
+}
+
+/// `dest ^= src << shift`, truncated to the length of `dest`.
+fn xor_shifted(dest: &mut [u64], src: &[u64], shift: usize) {
+    let q = shift / 64;
+    let r = shift % 64;
+    for i in (q..dest.len()).rev() {
+        let j = i - q;
+        let mut x = src.get(j).cloned().unwrap_or(0) << r;
+        if r != 0 && j > 0 {
+            x |= src[j - 1] >> (64 - r);
+        }
+        dest[i] ^= x;
+    }
+}
+
+/// `x <<= shift`, truncated to the length of `x`.
+fn shift_left(x: &mut [u64], shift: usize) {
+    let q = shift / 64;
+    let r = shift % 64;
+    for i in (0..x.len()).rev() {
+        let mut y = if i >= q { x[i - q] << r } else { 0 };
+        if r != 0 && i > q {
+            y |= x[i - q - 1] >> (64 - r);
+        }
+        x[i] = y;
+    }
+}
+
+/// The Berlekamp-Massey algorithm on the first `n` bits of `bits`, packed
+/// with bit `i` of the sequence in bit `i % 64` of word `i / 64`.
+///
+/// Returns the linear complexity `L`, and leaves the connection polynomial
+/// `C(x) = 1 + c_1 x + .. + c_L x^L` in `c`, with bit `i` for `c_i`, so that
+/// `s_j = c_1 s_(j-1) + .. + c_L s_(j-L)` for `L <= j < n`. `step` is called
+/// with the linear complexity after every bit.
+///
+/// `rev`, `c` and `b` are work space of at least `n / 64 + 2` words each, so
+/// that this works without allocating.
+pub fn berlekamp_massey<F>(bits: &[u64], n: usize,
+                           rev: &mut [u64], c: &mut [u64], b: &mut [u64],
+                           mut step: F) -> usize
+    where F: FnMut(usize)
+{
+    let words = n / 64 + 2;
+    assert!(rev.len() >= words && c.len() >= words && b.len() >= words);
+    let (rev, c, b) = (&mut rev[..words], &mut c[..words], &mut b[..words]);
+
+    // The sequence reversed, so that the discrepancy is a dot product of two
+    // runs of bits in the same order.
+    for x in rev.iter_mut().chain(c.iter_mut()).chain(b.iter_mut()) {
+        *x = 0;
+    }
+    for i in 0..n {
+        let bit = (bits[i / 64] >> (i % 64)) & 1;
+        rev[(n - 1 - i) / 64] |= bit << ((n - 1 - i) % 64);
+    }
+
+    c[0] = 1;
+    b[0] = 1;
+    let mut l = 0;
+    let mut m = 0; // steps since the last length change
+    for i in 0..n {
+        m += 1;
+        // d = sum_{j=0..L} c_j s_(i-j), and s_(i-j) is bit n-1-i+j of `rev`.
+        let start = n - 1 - i;
+        let mut d = 0;
+        for (w, cw) in c[..l / 64 + 1].iter().enumerate() {
+            d ^= cw & word_at(rev, start + w * 64);
+        }
+        if d.count_ones() & 1 != 0 {
+            // C(x) = C(x) + x^m B(x)
+            xor_shifted(c, b, m);
+            if 2 * l <= i {
+                // The new B(x) is the old C(x), which is the new C(x) plus
+                // x^m times the old B(x).
+                shift_left(b, m);
+                for (bw, cw) in b.iter_mut().zip(c.iter()) {
+                    *bw ^= cw;
+                }
+                l = i + 1 - l;
+                m = 0;
+            }
+        }
+        step(l);
+    }
+    l
+}
+
+/// The minimal polynomial of the first `n` bits of `bits`, found with the
+/// Berlekamp-Massey algorithm. `n` is at most 256.
+///
+/// For the sequence of one bit of the state of a linear RNG with `n / 2` bits
+/// of state, this is the characteristic polynomial if it has degree `n / 2`.
+///
+/// Panics if the linear complexity of the bits is more than 128.
+pub fn minimal_polynomial(bits: &[u64; 4], n: usize) -> Poly {
+    assert!(n <= 256);
+    let mut rev = [0u64; 6];
+    let mut c = [0u64; 6];
+    let mut b = [0u64; 6];
+    let l = berlekamp_massey(bits, n, &mut rev, &mut c, &mut b, |_| ());
+    assert!(l <= 128, "the linear complexity is too large");
+
+    // The connection polynomial 1 + c_1 x + .. + c_L x^L is the reciprocal
+    // of the minimal polynomial x^L + c_1 x^(L-1) + .. + c_L.
+    let mut low = 0u128;
+    for i in 1..l + 1 {
+        low |= (((c[i / 64] >> (i % 64)) & 1) as u128) << (l - i);
+    }
+    Poly::new(l as u32, low)
+}
+
+/// The polynomial `x^k` modulo the characteristic polynomial of `R`, packed
+/// with bit `i` for the coefficient of `x^i`.
+///
+/// Panics if the characteristic polynomial can not be determined.
+pub fn jump_polynomial<R: F2LinearRng>(k: u128) -> u128 {
+    // The same sequence as `analysis::f2::characteristic_polynomial` uses,
+    // but found by stepping the RNG instead of through its matrix.
+    let n = R::STATE_BITS as usize;
+    let mut bits = [0u64; 4];
+    let mut rng = R::from_state(1);
+    for i in 0..2 * n {
+        bits[i / 64] |= ((rng.state() & 1) as u64) << (i % 64);
+        rng.step();
+    }
+    let polynomial = minimal_polynomial(&bits, 2 * n);
+    assert!(polynomial.degree() == R::STATE_BITS,
+            "the characteristic polynomial could not be determined");
+    polynomial.pow_x_mod(k)
+}
+
+/// Jump ahead with a polynomial from `jump_polynomial`.
+///
+/// By the Cayley-Hamilton theorem `M^k` equals `J(M)`, where `J(x)` is `x^k`
+/// modulo the characteristic polynomial of the transition matrix `M`. So the
+/// new state is the xor of the states after `i` steps, for every term `x^i`
+/// of `J`.
+pub fn jump_with<R: F2LinearRng>(rng: &mut R, jump: u128) {
+    let mut state = 0;
+    for i in 0..R::STATE_BITS {
+        if (jump >> i) & 1 != 0 {
+            state ^= rng.state();
+        }
+        rng.step();
+    }
+    *rng = R::from_state(state);
+}
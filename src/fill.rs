@@ -0,0 +1,305 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Filling byte and integer slices.
+//!
+//! `fill_bytes_via_u32` and `fill_bytes_via_u64` produce the same bytes as
+//! `impls::fill_bytes_via_next` for generators with 32- and 64-bit words, but
+//! write the words directly in little-endian order and handle four words per
+//! iteration.
+//!
+//! The multi-lane generators produce a block of words per step and buffer it.
+//! `next_word_via_blocks`, `fill_words_via_blocks` and `fill_bytes_via_blocks`
+//! return the words of a `LaneBlocks` generator in order, using up the buffer
+//! before taking whole blocks.
+
+use core::cmp;
+use rand_core::RngCore;
+
+/// Write `x` to the first four bytes of `dest` in little-endian order.
+#[inline(always)]
+pub fn write_u32_le(dest: &mut [u8], x: u32) {
+    let dest = &mut dest[..4];
+    dest[0] = x as u8;
+    dest[1] = (x >> 8) as u8;
+    dest[2] = (x >> 16) as u8;
+    dest[3] = (x >> 24) as u8;
+}
+
+/// Write `x` to the first eight bytes of `dest` in little-endian order.
+#[inline(always)]
+pub fn write_u64_le(dest: &mut [u8], x: u64) {
+    write_u32_le(&mut dest[..4], x as u32);
+    write_u32_le(&mut dest[4..8], (x >> 32) as u32);
+}
+
+/// Implement `fill_bytes` via `next_u32`.
+///
+/// If `dest` is not a multiple of four bytes, the last word is truncated and
+/// its upper bytes are discarded.
+#[inline]
+pub fn fill_bytes_via_u32<R: RngCore + ?Sized>(rng: &mut R, dest: &mut [u8]) {
+    let mut left = dest;
+    while left.len() >= 16 {
+        let (chunk, rest) = {left}.split_at_mut(16);
+        left = rest;
+        write_u32_le(&mut chunk[0..4], rng.next_u32());
+        write_u32_le(&mut chunk[4..8], rng.next_u32());
+        write_u32_le(&mut chunk[8..12], rng.next_u32());
+        write_u32_le(&mut chunk[12..16], rng.next_u32());
+    }
+    while left.len() >= 4 {
+        let (chunk, rest) = {left}.split_at_mut(4);
+        left = rest;
+        write_u32_le(chunk, rng.next_u32());
+    }
+    if !left.is_empty() {
+        let x = rng.next_u32();
+        for (i, byte) in left.iter_mut().enumerate() {
+            *byte = (x >> (i * 8)) as u8;
+        }
+    }
+}
+
+/// Implement `fill_bytes` via `next_u64`.
+///
+/// If `dest` is not a multiple of eight bytes, the last word is truncated and
+/// its upper bytes are discarded.
+#[inline]
+pub fn fill_bytes_via_u64<R: RngCore + ?Sized>(rng: &mut R, dest: &mut [u8]) {
+    let mut left = dest;
+    while left.len() >= 32 {
+        let (chunk, rest) = {left}.split_at_mut(32);
+        left = rest;
+        write_u64_le(&mut chunk[0..8], rng.next_u64());
+        write_u64_le(&mut chunk[8..16], rng.next_u64());
+        write_u64_le(&mut chunk[16..24], rng.next_u64());
+        write_u64_le(&mut chunk[24..32], rng.next_u64());
+    }
+    while left.len() >= 8 {
+        let (chunk, rest) = {left}.split_at_mut(8);
+        left = rest;
+        write_u64_le(chunk, rng.next_u64());
+    }
+    if !left.is_empty() {
+        let x = rng.next_u64();
+        for (i, byte) in left.iter_mut().enumerate() {
+            *byte = (x >> (i * 8)) as u8;
+        }
+    }
+}
+
+/// Fill slices of integers directly, without going through bytes.
+///
+/// The slices are filled with exactly the same values as repeated calls to
+/// `next_u32` or `next_u64` would return. The default methods do just that;
+/// generators with an internal buffer or a state that is cheap to keep in
+/// registers override them.
+///
+/// `fill_u128` builds every value from two calls to `next_u64`, the low half
+/// first.
+pub trait FillWords: RngCore {
+    /// Fill `dest` with the values of `dest.len()` calls to `next_u32`.
+    fn fill_u32(&mut self, dest: &mut [u32]) {
+        for x in dest.iter_mut() {
+            *x = self.next_u32();
+        }
+    }
+
+    /// Fill `dest` with the values of `dest.len()` calls to `next_u64`.
+    fn fill_u64(&mut self, dest: &mut [u64]) {
+        for x in dest.iter_mut() {
+            *x = self.next_u64();
+        }
+    }
+
+    /// Fill `dest` with values made of two calls to `next_u64` each, the
+    /// first call giving the low half.
+    fn fill_u128(&mut self, dest: &mut [u128]) {
+        for x in dest.iter_mut() {
+            let lo = self.next_u64() as u128;
+            let hi = self.next_u64() as u128;
+            *x = (hi << 64) | lo;
+        }
+    }
+}
+
+/// A word of a `LaneBlocks` generator.
+pub trait LaneWord: Copy {
+    /// The size of the word in bytes.
+    const BYTES: usize;
+
+    fn to_u64(self) -> u64;
+}
+
+impl LaneWord for u32 {
+    const BYTES: usize = 4;
+
+    #[inline(always)]
+    fn to_u64(self) -> u64 { self as u64 }
+}
+
+impl LaneWord for u64 {
+    const BYTES: usize = 8;
+
+    #[inline(always)]
+    fn to_u64(self) -> u64 { self }
+}
+
+/// A multi-lane generator, that advances all lanes at once and buffers the
+/// resulting block of words.
+pub trait LaneBlocks {
+    type Word: LaneWord;
+    type Block: AsRef<[Self::Word]>;
+
+    /// Advance all lanes by one step, and return the output of each lane.
+    fn next_block(&mut self) -> Self::Block;
+
+    /// The buffered block, and the index of its first unused word.
+    fn buffer(&mut self) -> (&mut Self::Block, &mut usize);
+}
+
+/// Whether a `LaneBlocks` generator has unused words in its buffer.
+#[inline]
+fn has_buffered<R: LaneBlocks + ?Sized>(rng: &mut R) -> bool {
+    let (buffer, index) = rng.buffer();
+    *index < buffer.as_ref().len()
+}
+
+/// The next word of a `LaneBlocks` generator: lane 0, 1, .. of every block in
+/// turn.
+#[inline]
+pub fn next_word_via_blocks<R: LaneBlocks + ?Sized>(rng: &mut R) -> R::Word {
+    if !has_buffered(rng) {
+        let block = rng.next_block();
+        let (buffer, index) = rng.buffer();
+        *buffer = block;
+        *index = 0;
+    }
+    let (buffer, index) = rng.buffer();
+    *index += 1;
+    buffer.as_ref()[*index - 1]
+}
+
+/// Implement `fill_u32` or `fill_u64` of a `LaneBlocks` generator, with the
+/// same values as `next_word_via_blocks`.
+pub fn fill_words_via_blocks<R: LaneBlocks + ?Sized>(rng: &mut R, dest: &mut [R::Word]) {
+    // Use up the buffered words first.
+    let mut left = dest;
+    while !left.is_empty() && has_buffered(rng) {
+        let (first, rest) = {left}.split_at_mut(1);
+        first[0] = next_word_via_blocks(rng);
+        left = rest;
+    }
+    let block_len = rng.buffer().0.as_ref().len();
+    let mut chunks = left.chunks_exact_mut(block_len);
+    for chunk in &mut chunks {
+        chunk.copy_from_slice(rng.next_block().as_ref());
+    }
+    for x in chunks.into_remainder() {
+        *x = next_word_via_blocks(rng);
+    }
+}
+
+/// Write the lowest `dest.len()` bytes of `x` in little-endian order.
+#[inline(always)]
+fn write_word_le(dest: &mut [u8], x: u64) {
+    for (i, byte) in dest.iter_mut().enumerate() {
+        *byte = (x >> (i * 8)) as u8;
+    }
+}
+
+/// Implement `fill_bytes` of a `LaneBlocks` generator, with the same bytes as
+/// `fill_bytes_via_u32` or `fill_bytes_via_u64` over `next_word_via_blocks`.
+pub fn fill_bytes_via_blocks<R: LaneBlocks + ?Sized>(rng: &mut R, dest: &mut [u8]) {
+    let bytes = R::Word::BYTES;
+    // Use up the buffered words first.
+    let mut left = dest;
+    while !left.is_empty() && has_buffered(rng) {
+        let n = cmp::min(bytes, left.len());
+        let (first, rest) = {left}.split_at_mut(n);
+        write_word_le(first, next_word_via_blocks(rng).to_u64());
+        left = rest;
+    }
+    let block_len = rng.buffer().0.as_ref().len();
+    let mut chunks = left.chunks_exact_mut(block_len * bytes);
+    for chunk in &mut chunks {
+        let block = rng.next_block();
+        for (word, x) in chunk.chunks_exact_mut(bytes).zip(block.as_ref().iter()) {
+            write_word_le(word, x.to_u64());
+        }
+    }
+    for word in chunks.into_remainder().chunks_mut(bytes) {
+        write_word_le(word, next_word_via_blocks(rng).to_u64());
+    }
+}
+
+/// Fill `seed` with a fixed pattern, for the tests of the multi-lane
+/// generators.
+#[cfg(test)]
+pub fn lanes_test_seed(seed: &mut [u8]) {
+    for (i, x) in seed.iter_mut().enumerate() {
+        *x = (i as u8).wrapping_mul(97).wrapping_add(13);
+    }
+}
+
+/// Check that every block of `rng` is the next output of each of `lanes`.
+#[cfg(test)]
+pub fn check_lanes<R, L, F>(mut rng: R, mut lanes: Vec<L>, mut next: F)
+    where R: LaneBlocks, R::Word: PartialEq + ::core::fmt::Debug,
+          F: FnMut(&mut L) -> R::Word
+{
+    for _ in 0..100 {
+        let block = rng.next_block();
+        assert_eq!(block.as_ref().len(), lanes.len());
+        for (lane, &x) in lanes.iter_mut().zip(block.as_ref().iter()) {
+            assert_eq!(x, next(lane));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use {Jsf32x8Rng, Sfc64x4Rng, Xoroshiro128Plusx4Rng};
+    use super::*;
+
+    /// `fill_bytes` must give the same bytes as the words one at a time, also
+    /// in the middle of a block.
+    fn check_fill_bytes_after_next<R>()
+        where R: LaneBlocks + RngCore + SeedableRng + Clone
+    {
+        for skip in 0..10 {
+            let mut rng1 = R::seed_from_u64(skip);
+            for _ in 0..skip {
+                next_word_via_blocks(&mut rng1);
+            }
+            let mut rng2 = rng1.clone();
+            let mut bytes1 = [0u8; 101];
+            let mut bytes2 = [0u8; 101];
+            rng1.fill_bytes(&mut bytes1);
+            if R::Word::BYTES == 4 {
+                fill_bytes_via_u32(&mut rng2, &mut bytes2);
+            } else {
+                fill_bytes_via_u64(&mut rng2, &mut bytes2);
+            }
+            assert_eq!(&bytes1[..], &bytes2[..]);
+            assert_eq!(next_word_via_blocks(&mut rng1).to_u64(),
+                       next_word_via_blocks(&mut rng2).to_u64());
+        }
+    }
+
+    #[test]
+    fn test_fill_bytes_via_blocks() {
+        check_fill_bytes_after_next::<Jsf32x8Rng>();
+        check_fill_bytes_after_next::<Sfc64x4Rng>();
+        check_fill_bytes_after_next::<Xoroshiro128Plusx4Rng>();
+    }
+}
+
@@ -0,0 +1,76 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Linear algebra over GF(2).
+//!
+//! Bit sequences are packed into `u64` words, with bit `i` of the sequence in
+//! bit `i % 64` of word `i / 64`.
+
+use f2_linear;
+
+/// Rank of a matrix with up to 64 columns, given as one word per row.
+///
+/// Every row is reduced by a basis of the previous rows, indexed by their
+/// highest set bit; the rank is the size of the basis.
+pub fn rank(rows: &[u64]) -> usize {
+    let mut basis = [0u64; 64];
+    let mut rank = 0;
+    for &row in rows {
+        let mut x = row;
+        while x != 0 {
+            let high = 63 - x.leading_zeros() as usize;
+            if basis[high] == 0 {
+                basis[high] = x;
+                rank += 1;
+                break;
+            }
+            x ^= basis[high];
+        }
+    }
+    rank
+}
+
+/// Linear complexity of the first `n` bits of `bits`: the length of the
+/// shortest LFSR that generates them, using the Berlekamp-Massey algorithm.
+pub fn linear_complexity(bits: &[u64], n: usize) -> usize {
+    let (l, _) = berlekamp_massey(bits, n);
+    l
+}
+
+/// The Berlekamp-Massey algorithm.
+///
+/// Returns the linear complexity `L` of the first `n` bits of `bits`, and the
+/// connection polynomial `C(x) = 1 + c_1 x + .. + c_L x^L` packed with bit `i`
+/// for `c_i`, so that `s_j = c_1 s_(j-1) + .. + c_L s_(j-L)` for `L <= j < n`.
+pub fn berlekamp_massey(bits: &[u64], n: usize) -> (usize, Vec<u64>) {
+    berlekamp_massey_with(bits, n, |_| ())
+}
+
+/// The linear complexity profile of the first `n` bits of `bits`: the linear
+/// complexity of every prefix, from length 1 to `n`.
+pub fn linear_complexity_profile(bits: &[u64], n: usize) -> Vec<usize> {
+    let mut profile = Vec::with_capacity(n);
+    berlekamp_massey_with(bits, n, |l| profile.push(l));
+    profile
+}
+
+/// The Berlekamp-Massey algorithm, calling `step` with the linear complexity
+/// after every bit.
+fn berlekamp_massey_with<F>(bits: &[u64], n: usize, step: F)
+    -> (usize, Vec<u64>)
+    where F: FnMut(usize)
+{
+    let words = n / 64 + 2;
+    let mut rev = vec![0u64; words];
+    let mut c = vec![0u64; words];
+    let mut b = vec![0u64; words];
+    let l = f2_linear::berlekamp_massey(bits, n, &mut rev, &mut c, &mut b, step);
+    c.truncate(l / 64 + 1);
+    (l, c)
+}
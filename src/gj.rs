@@ -9,7 +9,9 @@
 
 //! Geronimo Jones' random number generator.
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
 
 /// A small random number generator by Geronimo Jones.
 ///
@@ -35,6 +37,19 @@ impl SeedableRng for GjRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for GjRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 14,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
 
@@ -42,14 +57,14 @@ impl SeedableRng for GjRng {
                                 b: seed_u64[1],
                                 c: 5000001,
                                 d: 0};
-        for _ in 0..14 {
+        for _ in 0..policy.warm_up {
             state.next_u64();
         }
         state
     }
 }
 
-impl Rng for GjRng {
+impl RngCore for GjRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -70,21 +85,19 @@ impl Rng for GjRng {
         self.a = self.a.wrapping_add(self.c);
         self.b = self.b.rotate_left(19);
         self.c = self.c.wrapping_add(self.a);
-        self.b += self.d;
+        self.b = self.b.wrapping_add(self.d);
 
         self.a
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
+
+impl FillWords for GjRng {}
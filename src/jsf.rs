@@ -9,7 +9,9 @@
 
 //! Bob Jenkins small fast pseudorandom number generator.
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords, LaneBlocks};
 
 /// A small random number generator designed by Bob Jenkins.
 ///
@@ -33,6 +35,19 @@ impl SeedableRng for Jsf32Rng {
     type Seed = [u8; 4];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Jsf32Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 20,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 1];
         le::read_u32_into(&seed, &mut seed_u32);
 
@@ -40,14 +55,14 @@ impl SeedableRng for Jsf32Rng {
                                b: seed_u32[0],
                                c: seed_u32[0],
                                d: seed_u32[0]};
-        for _ in 0..20 {
+        for _ in 0..policy.warm_up {
             state.next_u32();
         }
         state
     }
 }
 
-impl Rng for Jsf32Rng {
+impl RngCore for Jsf32Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         let e = self.a.wrapping_sub(self.b.rotate_left(27));
@@ -63,20 +78,18 @@ impl Rng for Jsf32Rng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl FillWords for Jsf32Rng {}
+
 
 
 
@@ -102,6 +115,19 @@ impl SeedableRng for Jsf64Rng {
     type Seed = [u8; 8];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Jsf64Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 20,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 1];
         le::read_u64_into(&seed, &mut seed_u64);
 
@@ -109,14 +135,14 @@ impl SeedableRng for Jsf64Rng {
                                b: seed_u64[0],
                                c: seed_u64[0],
                                d: seed_u64[0]};
-        for _ in 0..20 {
+        for _ in 0..policy.warm_up {
             state.next_u64();
         }
         state
     }
 }
 
-impl Rng for Jsf64Rng {
+impl RngCore for Jsf64Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -132,16 +158,144 @@ impl Rng for Jsf64Rng {
         self.d
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u64(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Jsf64Rng {}
+
+
+
+/// Eight independent `Jsf32Rng` streams, advanced in lock-step.
+///
+/// The state is stored as one array per variable, so the compiler can keep all
+/// eight lanes in vector registers. Lane `i` produces exactly the same outputs
+/// as the `Jsf32Rng` it was constructed from.
+///
+/// Because the word size of JSF is 32 bits, this exposes `next_u32x8` instead
+/// of a 64-bit variant. The `RngCore` methods return lane 0 to 7 of every step in
+/// turn.
+///
+/// The seed is the seeds of the eight lanes, one after the other.
+#[derive(Clone)]
+pub struct Jsf32x8Rng {
+    a: [u32; 8],
+    b: [u32; 8],
+    c: [u32; 8],
+    d: [u32; 8],
+    results: [u32; 8],
+    index: usize,
+}
+
+impl Jsf32x8Rng {
+    /// Combine eight scalar generators into one multi-lane generator.
+    pub fn from_lanes(lanes: [Jsf32Rng; 8]) -> Self {
+        let mut state = Self { a: [0; 8], b: [0; 8], c: [0; 8], d: [0; 8],
+                               results: [0; 8], index: 8 };
+        for (i, lane) in lanes.iter().enumerate() {
+            state.a[i] = lane.a;
+            state.b[i] = lane.b;
+            state.c[i] = lane.c;
+            state.d[i] = lane.d;
+        }
+        state
+    }
+
+    /// Advance all lanes by one step, and return the output of each lane.
+    #[inline]
+    pub fn next_u32x8(&mut self) -> [u32; 8] {
+        for i in 0..8 {
+            let e = self.a[i].wrapping_sub(self.b[i].rotate_left(27));
+            self.a[i] = self.b[i] ^ self.c[i].rotate_left(17);
+            self.b[i] = self.c[i].wrapping_add(self.d[i]);
+            self.c[i] = self.d[i].wrapping_add(e);
+            self.d[i] = e.wrapping_add(self.a[i]);
+        }
+        self.d
+    }
+}
+
+impl SeedableRng for Jsf32x8Rng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let lane = |i: usize| {
+            let mut lane_seed = [0u8; 4];
+            lane_seed.copy_from_slice(&seed[i * 4..(i + 1) * 4]);
+            Jsf32Rng::from_seed(lane_seed)
+        };
+        Self::from_lanes([lane(0), lane(1), lane(2), lane(3),
+                          lane(4), lane(5), lane(6), lane(7)])
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl LaneBlocks for Jsf32x8Rng {
+    type Word = u32;
+    type Block = [u32; 8];
+
+    #[inline]
+    fn next_block(&mut self) -> [u32; 8] {
+        self.next_u32x8()
+    }
+
+    #[inline]
+    fn buffer(&mut self) -> (&mut [u32; 8], &mut usize) {
+        (&mut self.results, &mut self.index)
+    }
+}
+
+impl RngCore for Jsf32x8Rng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        fill::next_word_via_blocks(self)
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest)
+        fill::fill_bytes_via_blocks(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Jsf32x8Rng {
+    fn fill_u32(&mut self, dest: &mut [u32]) {
+        fill::fill_words_via_blocks(self, dest)
     }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use fill;
+    use super::*;
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    #[test]
+    fn test_jsf32x8_lanes() {
+        let mut seed = [0u8; 32];
+        fill::lanes_test_seed(&mut seed);
+        let lanes: Vec<Jsf32Rng> = seed.chunks(4).map(|chunk| {
+            let mut lane_seed = [0u8; 4];
+            lane_seed.copy_from_slice(chunk);
+            Jsf32Rng::from_seed(lane_seed)
+        }).collect();
+        fill::check_lanes(Jsf32x8Rng::from_seed(seed), lanes, |lane| lane.next_u32());
     }
 }
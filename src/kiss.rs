@@ -10,7 +10,9 @@
 //! KISS rondom number generators
 
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
 use core::fmt;
 use core::num::Wrapping as Wr;
 
@@ -19,7 +21,7 @@ use core::num::Wrapping as Wr;
 /// - Author: George Marsaglia
 /// - License: Public domain
 /// - Source: ["Random numbers in C: Some suggestions"]
-///           (http://www.ciphersbyritter.com/NEWS4/RANDC.HTM).
+///   (http://www.ciphersbyritter.com/NEWS4/RANDC.HTM).
 /// - Period: ~2<sup>123</sup>
 /// - State: 128 bits
 /// - Word size: 32 bits
@@ -43,29 +45,36 @@ impl SeedableRng for Kiss32Rng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Kiss32Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 4];
         le::read_u32_into(&seed, &mut seed_u32);
 
-        Self {
+        let mut state = Self {
             z: Wr(seed_u32[0]),
             w: Wr(seed_u32[1]),
-            jsr: Wr(if seed_u32[2] != 0 { seed_u32[2] } else { 0xBAD_5EED }),
+            jsr: Wr(if seed_u32[2] != 0 || !policy.check_bad_state { seed_u32[2] }
+                    else { 0xBAD_5EED }),
             jcong: Wr(seed_u32[3]),
+        };
+        for _ in 0..policy.warm_up {
+            state.next_u32();
         }
-    }
-
-    fn from_rng<R: Rng>(mut rng: R) -> Result<Self, Error> {
-        let z = rng.next_u32();
-        let w = rng.next_u32();
-        let mut jsr = 0;
-        while jsr == 0 { jsr = rng.next_u32() };
-        let jcong = rng.next_u32();
-
-        Ok(Kiss32Rng { z: Wr(z), w: Wr(w), jsr: Wr(jsr), jcong: Wr(jcong) })
+        state
     }
 }
 
-impl Rng for Kiss32Rng {
+impl RngCore for Kiss32Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         // Multiply-With-Carry (MWC)
@@ -89,27 +98,25 @@ impl Rng for Kiss32Rng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest);
+        fill::fill_bytes_via_u32(self, dest);
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl FillWords for Kiss32Rng {}
+
 
 /// The KISS random number generator (64-bit variant).
 ///
 /// - Author: George Marsaglia
 /// - License: Public domain
 /// - Source: ["64-bit KISS RNGs"]
-///           (https://www.thecodingforums.com/threads/64-bit-kiss-rngs.673657/).
+///   (https://www.thecodingforums.com/threads/64-bit-kiss-rngs.673657/).
 /// - Period: ~2<sup>247</sup>
 /// - State: 256 bits
 /// - Word size: 64 bits
@@ -133,30 +140,36 @@ impl SeedableRng for Kiss64Rng {
     type Seed = [u8; 32];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Kiss64Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 4];
         le::read_u64_into(&seed, &mut seed_u64);
 
-        Self {
+        let mut state = Self {
             c: Wr(seed_u64[0]),
             x: Wr(seed_u64[1]),
-            y: Wr(if seed_u64[2] != 0 { seed_u64[2] }
-                  else { 0x0DD_B1A5E5_BAD_5EED }),
+            y: Wr(if seed_u64[2] != 0 || !policy.check_bad_state { seed_u64[2] }
+                  else { seed_policy::BAD_SEED_REPLACEMENT }),
             z: Wr(seed_u64[3]),
+        };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
         }
-    }
-
-    fn from_rng<R: Rng>(mut rng: R) -> Result<Self, Error> {
-        let c = rng.next_u64();
-        let x = rng.next_u64();
-        let mut y = 0;
-        while y == 0 { y = rng.next_u64() };
-        let z = rng.next_u64();
-
-        Ok(Kiss64Rng { c: Wr(c), x: Wr(x), y: Wr(y), z: Wr(z) })
+        state
     }
 }
 
-impl Rng for Kiss64Rng {
+impl RngCore for Kiss64Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -180,16 +193,14 @@ impl Rng for Kiss64Rng {
         (self.x + self.y + self.z).0
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest);
+        fill::fill_bytes_via_u64(self, dest);
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
+
+impl FillWords for Kiss64Rng {}
@@ -1,8 +1,40 @@
-#![feature(i128_type)]
+//! Small, fast random number generators.
+//!
+//! The generators only need `core`, and so does `jump_by` of the linear
+//! generators. With the default `std` feature the crate also has the tools to
+//! analyse and test them: the registry, the test battery, and the analysis of
+//! the linear generators.
+//!
+//! All generators implement `RngCore` and `SeedableRng` from `rand_core`, so
+//! they work with everything in `rand` that takes an `Rng`, such as
+//! `rand::distributions` and `rand::seq`.
+//!
+//! `SmallRng` is the fastest high-quality generator for the target, for code
+//! that does not care which generator it uses.
+//!
+//! None of the generators is cryptographically secure, and none of them
+//! implements the `CryptoRng` marker trait. Code that needs a secure generator
+//! can require `CryptoRng`, and will not accept any of these by mistake.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(unsafe_code)]
 
 extern crate rand_core;
+#[cfg(feature = "std")]
 extern crate core;
 
+mod fill;
+mod f2_linear;
+mod seed_policy;
+#[cfg(feature = "std")] mod stats;
+#[cfg(feature = "std")] mod gf2;
+
+#[cfg(feature = "std")] pub mod analysis;
+#[cfg(feature = "std")] pub mod battery;
+#[cfg(feature = "std")] pub mod registry;
+#[cfg(feature = "std")] pub mod scaled;
+#[cfg(feature = "std")] pub mod streams;
+
 mod ciprng;
 mod gj;
 mod jsf;
@@ -11,6 +43,7 @@ mod msws;
 mod pcg;
 mod sapparoth;
 mod sfc;
+mod small_rng;
 mod velox;
 mod xorshift;
 mod xorshift_plus;
@@ -19,18 +52,24 @@ mod xoroshiro;
 mod xoroshiro_mt;
 mod xsm;
 
+pub use self::f2_linear::F2LinearRng;
+pub use self::fill::FillWords;
+pub use self::seed_policy::{SeedPolicy, SeedWithPolicy};
+
 pub use self::ciprng::CiRng;
 pub use self::gj::GjRng;
-pub use self::jsf::{Jsf32Rng, Jsf64Rng};
+pub use self::jsf::{Jsf32Rng, Jsf64Rng, Jsf32x8Rng};
 pub use self::kiss::{Kiss32Rng, Kiss64Rng};
 pub use self::msws::MswsRng;
 pub use self::pcg::{PcgXsh64LcgRng, PcgXsl64LcgRng, PcgXsl128McgRng, MwpRng};
 pub use self::sapparoth::{Sapparot32Rng, Sapparot64Rng};
-pub use self::sfc::{Sfc32Rng, Sfc64Rng};
+pub use self::sfc::{Sfc32Rng, Sfc64Rng, Sfc64x4Rng, Sfc64x4Seed};
+pub use self::small_rng::SmallRng;
 pub use self::velox::Velox3bRng;
 pub use self::xorshift::{Xorshift128_32Rng, Xorshift128_64Rng};
 pub use self::xorshift_plus::Xorshift128PlusRng;
 pub use self::xorshift_mt::{XorshiftMt32Rng, XorshiftMt64Rng};
-pub use self::xoroshiro::{Xoroshiro128PlusRng, Xoroshiro64PlusRng};
+pub use self::xoroshiro::{Xoroshiro128PlusRng, Xoroshiro64PlusRng,
+                          Xoroshiro128Plusx4Rng, Xoroshiro128Plusx4Seed};
 pub use self::xoroshiro_mt::{XoroshiroMt32of128Rng, XoroshiroMt64of128Rng};
 pub use self::xsm::{Xsm32Rng, Xsm64Rng};
@@ -9,7 +9,9 @@
 
 //! Middle Square Weyl Sequence RNG
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
 
 /// Middle Square Weyl Sequence RNG
 ///
@@ -18,7 +20,7 @@ use rand_core::{Rng, SeedableRng, Error, impls, le};
 /// - Source: https://mswsrng.wixsite.com/rand
 /// - Period: 2<sup>64</sup>
 /// - State: 192 bits
-/// - Word size: 64 bits
+/// - Word size: 32 bits
 /// - Seed size: 128 bits
 #[derive(Clone)]
 pub struct MswsRng {
@@ -31,51 +33,57 @@ impl SeedableRng for MswsRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for MswsRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
         // The constant s should be set to a random 64-bit pattern with the
         // upper 32 bits non-zero and the least significant bit set to 1
-        let stream = seed_u64[0] | 1;
-        if stream & 0xffffffff_00000000 != 0 { panic!(); }
-        Self { x: seed_u64[1], w: 0, s: stream }
-    }
-
-    fn from_rng<R: Rng>(mut other: R) -> Result<Self, Error> {
-        let mut stream;
-        loop {
-            // The constant s should be set to a random 64-bit pattern with the
-            // upper 32 bits non-zero and the least significant bit set to 1
-            stream = other.next_u64() | 1;
-            if stream & 0xffffffff_00000000 != 0 { break; }
+        let mut stream = seed_u64[0] | 1;
+        if policy.check_bad_state && stream & 0xffffffff_00000000 == 0 {
+            stream |= 0xb5ad4ece_00000000;
         }
-        Ok(Self { x: other.next_u64(), w: 0, s: stream })
+        let mut state = Self { x: seed_u64[1], w: 0, s: stream };
+        for _ in 0..policy.warm_up {
+            state.next_u32();
+        }
+        state
     }
 }
 
-impl Rng for MswsRng {
+impl RngCore for MswsRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
-        self.next_u64() as u32
-    }
-
-    #[inline]
-    fn next_u64(&mut self) -> u64 {
         self.x = self.x.wrapping_mul(self.x);
         self.w = self.w.wrapping_add(self.s);
         self.x = self.x.wrapping_add(self.w);
-        self.x.rotate_left(32)
+        // Only the middle of the square is a good output.
+        (self.x >> 32) as u32
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
+
+impl FillWords for MswsRng {}
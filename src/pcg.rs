@@ -9,7 +9,9 @@
 
 //! PCG random number generators
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
 
 /// A PCG random number generator (XSH 64/32 (LCG) variant).
 ///
@@ -25,6 +27,18 @@ impl SeedableRng for PcgXsh64LcgRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for PcgXsh64LcgRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::NONE;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
         // We only have to make sure increment is odd.
@@ -33,11 +47,14 @@ impl SeedableRng for PcgXsh64LcgRng {
         // Prepare for the first round
         ctx.state = ctx.state.wrapping_mul(6364136223846793005)
                              .wrapping_add(ctx.increment);
+        for _ in 0..policy.warm_up {
+            ctx.next_u32();
+        }
         ctx
     }
 }
 
-impl Rng for PcgXsh64LcgRng {
+impl RngCore for PcgXsh64LcgRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         let state = self.state;
@@ -64,20 +81,18 @@ impl Rng for PcgXsh64LcgRng {
        impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl FillWords for PcgXsh64LcgRng {}
+
 
 
 /// A PCG random number generator (XSL 64/32 (LCG) variant).
@@ -94,6 +109,18 @@ impl SeedableRng for PcgXsl64LcgRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for PcgXsl64LcgRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::NONE;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
         // We only have to make sure increment is odd.
@@ -102,11 +129,14 @@ impl SeedableRng for PcgXsl64LcgRng {
         // Prepare for the first round
         ctx.state = ctx.state.wrapping_mul(6364136223846793005)
                              .wrapping_add(ctx.increment);
+        for _ in 0..policy.warm_up {
+            ctx.next_u32();
+        }
         ctx
     }
 }
 
-impl Rng for PcgXsl64LcgRng {
+impl RngCore for PcgXsl64LcgRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         let state = self.state;
@@ -132,20 +162,18 @@ impl Rng for PcgXsl64LcgRng {
        impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl FillWords for PcgXsl64LcgRng {}
+
 
 
 /// A PCG random number generator (XSL 128/64 (MCG) variant).
@@ -163,18 +191,37 @@ impl SeedableRng for PcgXsl128McgRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for PcgXsl128McgRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
-        // We only have to make sure increment is odd.
         let mut ctx = Self { state: (seed_u64[0] as u128) << 64 |
                                     (seed_u64[1] as u128) };
+        // The state of an MCG has to be odd, otherwise the low bits stay
+        // zero and the period is reduced.
+        if policy.check_bad_state {
+            ctx.state |= 1;
+        }
         // Prepare for the first round
         ctx.state = ctx.state.wrapping_mul(MULTIPLIER);
+        for _ in 0..policy.warm_up {
+            ctx.next_u64();
+        }
         ctx
     }
 }
 
-impl Rng for PcgXsl128McgRng {
+impl RngCore for PcgXsl128McgRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -199,20 +246,18 @@ impl Rng for PcgXsl128McgRng {
         xsl.rotate_right((state >> ROTATE) as u32)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest)
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl FillWords for PcgXsl128McgRng {}
+
 
 
 #[derive(Clone)]
@@ -225,13 +270,29 @@ impl SeedableRng for MwpRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for MwpRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::NONE;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
-        Self { m: seed_u64[0] | 1, w: seed_u64[1] }
+        let mut state = Self { m: seed_u64[0] | 1, w: seed_u64[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
+        state
     }
 }
 
-impl Rng for MwpRng {
+impl RngCore for MwpRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.m = self.m.wrapping_mul(6364136223846793005);
@@ -264,23 +325,23 @@ impl Rng for MwpRng {
         const BITS: u64 = 64;
         const OP_BITS: u64 = 5; // log2(BITS)
         const MASK: u64 = BITS - 1;
+        #[allow(clippy::manual_div_ceil)] // as in the PCG reference code
+        const FINAL_SHIFT: u64 = (2 * BITS + 2) / 3;
 
         let rshift = (state >> (BITS - OP_BITS)) & MASK;
         state ^= state >> (OP_BITS + rshift);
         state = state.wrapping_mul(6364136223846793005);
-        state ^ (state >> ((2 * BITS + 2) / 3))
-    }
-
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+        state ^ (state >> FINAL_SHIFT)
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest)
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
+
+impl FillWords for MwpRng {}
@@ -0,0 +1,476 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A list of all seedable generators and their properties.
+//!
+//! The list itself is the `generators!` macro, so that the benchmarks can
+//! generate code for every type, and `GENERATORS` is built from it for tools
+//! that pick a generator at runtime. A new generator only has to be added to
+//! the macro.
+
+use core::mem;
+use rand_core::{RngCore, SeedableRng};
+use seed_policy;
+
+use super::*;
+
+/// Call the macro `$m` with the list of all seedable generators.
+///
+/// Every entry has the form `name: Type { field: value, .. }`, with the
+/// fields of `Generator` that can not be derived from the type, always in the
+/// same order. The test results are variants of `TestResult`.
+///
+/// `SmallRng` is a different generator depending on the pointer width, and so
+/// are its properties.
+#[macro_export]
+macro_rules! generators {
+    ($m:ident) => {
+        $m! {
+            ci: CiRng {
+                word_bits: 32, state_bits: 192, period: "unknown",
+                stream_offset: None, jump: false,
+                quality: "chaotic iterations, slow",
+                big_crush: Unknown, practrand: Unknown
+            },
+            gj: GjRng {
+                word_bits: 64, state_bits: 256, period: "2^64",
+                stream_offset: None, jump: false,
+                quality: "chaotic with a counter",
+                big_crush: Unknown, practrand: Unknown
+            },
+            jsf32: Jsf32Rng {
+                word_bits: 32, state_bits: 128, period: "2^64",
+                stream_offset: None, jump: false,
+                quality: "chaotic, small seed",
+                big_crush: Pass, practrand: Pass
+            },
+            jsf32x8: Jsf32x8Rng {
+                word_bits: 32, state_bits: 1024, period: "2^64",
+                stream_offset: None, jump: false,
+                quality: "eight jsf32 lanes, interleaved",
+                big_crush: Unknown, practrand: Unknown
+            },
+            jsf64: Jsf64Rng {
+                word_bits: 64, state_bits: 256, period: "2^64",
+                stream_offset: None, jump: false,
+                quality: "chaotic, small seed",
+                big_crush: Pass, practrand: Pass
+            },
+            kiss32: Kiss32Rng {
+                word_bits: 32, state_bits: 128, period: "~2^123",
+                stream_offset: None, jump: false,
+                quality: "combination of an LCG, xorshift and MWC",
+                big_crush: Pass, practrand: Unknown
+            },
+            kiss64: Kiss64Rng {
+                word_bits: 64, state_bits: 256, period: "~2^247",
+                stream_offset: None, jump: false,
+                quality: "combination of an LCG, xorshift and MWC",
+                big_crush: Unknown, practrand: Unknown
+            },
+            msws: MswsRng {
+                word_bits: 32, state_bits: 192, period: "2^64",
+                stream_offset: Some(0), jump: false,
+                quality: "the key must be a good random constant",
+                big_crush: Pass, practrand: Pass
+            },
+            mwp: MwpRng {
+                word_bits: 64, state_bits: 128, period: "2^64",
+                stream_offset: None, jump: false,
+                quality: "experimental: MCG combined with a Weyl sequence",
+                big_crush: Unknown, practrand: Unknown
+            },
+            pcg_xsh_64_lcg: PcgXsh64LcgRng {
+                word_bits: 32, state_bits: 128, period: "2^64",
+                stream_offset: Some(8), jump: false,
+                quality: "LCG with permuted output",
+                big_crush: Pass, practrand: Pass
+            },
+            pcg_xsl_64_lcg: PcgXsl64LcgRng {
+                word_bits: 32, state_bits: 128, period: "2^64",
+                stream_offset: Some(8), jump: false,
+                quality: "LCG with permuted output",
+                big_crush: Pass, practrand: Pass
+            },
+            pcg_xsl_128_mcg: PcgXsl128McgRng {
+                word_bits: 64, state_bits: 128, period: "2^126",
+                stream_offset: None, jump: false,
+                quality: "MCG with permuted output",
+                big_crush: Pass, practrand: Pass
+            },
+            sapparoth_32: Sapparot32Rng {
+                word_bits: 32, state_bits: 96, period: "unknown",
+                stream_offset: None, jump: false,
+                quality: "chaotic",
+                big_crush: Unknown, practrand: Unknown
+            },
+            sapparoth_64: Sapparot64Rng {
+                word_bits: 64, state_bits: 192, period: "unknown",
+                stream_offset: None, jump: false,
+                quality: "chaotic",
+                big_crush: Unknown, practrand: Unknown
+            },
+            sfc_32: Sfc32Rng {
+                word_bits: 32, state_bits: 128, period: ">= 2^32",
+                stream_offset: None, jump: false,
+                quality: "chaotic with a counter",
+                big_crush: Pass, practrand: Pass
+            },
+            sfc_64: Sfc64Rng {
+                word_bits: 64, state_bits: 256, period: ">= 2^64",
+                stream_offset: None, jump: false,
+                quality: "chaotic with a counter",
+                big_crush: Pass, practrand: Pass
+            },
+            sfc_64x4: Sfc64x4Rng {
+                word_bits: 64, state_bits: 1024, period: ">= 2^64",
+                stream_offset: None, jump: false,
+                quality: "four sfc_64 lanes, interleaved",
+                big_crush: Unknown, practrand: Unknown
+            },
+            small: SmallRng {
+                word_bits: if cfg!(target_pointer_width = "64") { 64 } else { 32 },
+                state_bits: if cfg!(target_pointer_width = "64") { 256 } else { 128 },
+                period: if cfg!(target_pointer_width = "64") { ">= 2^64" }
+                        else { ">= 2^32" },
+                stream_offset: None, jump: false,
+                quality: "sfc_64, or sfc_32 on targets without 64-bit pointers",
+                big_crush: Pass, practrand: Pass
+            },
+            velox: Velox3bRng {
+                word_bits: 32, state_bits: 256, period: ">= 2^128",
+                stream_offset: None, jump: false,
+                quality: "chaotic with a counter",
+                big_crush: Unknown, practrand: Unknown
+            },
+            xorshift_128_32: Xorshift128_32Rng {
+                word_bits: 32, state_bits: 128, period: "2^128 - 1",
+                stream_offset: None, jump: false,
+                quality: "linear, fails binary rank tests",
+                big_crush: Fail, practrand: Fail
+            },
+            xorshift_128_64: Xorshift128_64Rng {
+                word_bits: 64, state_bits: 128, period: "2^128 - 1",
+                stream_offset: None, jump: false,
+                quality: "linear, fails binary rank tests",
+                big_crush: Fail, practrand: Fail
+            },
+            xorshift_128_plus: Xorshift128PlusRng {
+                word_bits: 64, state_bits: 128, period: "2^128 - 1",
+                stream_offset: None, jump: true,
+                quality: "linear, weak low bits",
+                big_crush: Pass, practrand: Fail
+            },
+            xorshift_mt_32: XorshiftMt32Rng {
+                word_bits: 32, state_bits: 64, period: "2^64 - 1",
+                stream_offset: None, jump: false,
+                quality: "linear state, multiplied output",
+                big_crush: Unknown, practrand: Unknown
+            },
+            xorshift_mt_64: XorshiftMt64Rng {
+                word_bits: 64, state_bits: 128, period: "2^128 - 1",
+                stream_offset: None, jump: false,
+                quality: "linear state, multiplied output",
+                big_crush: Unknown, practrand: Unknown
+            },
+            xoroshiro_128_plus: Xoroshiro128PlusRng {
+                word_bits: 64, state_bits: 128, period: "2^128 - 1",
+                stream_offset: None, jump: true,
+                quality: "linear, weak low bits",
+                big_crush: Pass, practrand: Fail
+            },
+            xoroshiro_128_plus_x4: Xoroshiro128Plusx4Rng {
+                word_bits: 64, state_bits: 512, period: "2^128 - 1",
+                stream_offset: None, jump: false,
+                quality: "four xoroshiro_128_plus lanes, interleaved",
+                big_crush: Unknown, practrand: Unknown
+            },
+            xoroshiro_64_plus: Xoroshiro64PlusRng {
+                word_bits: 32, state_bits: 64, period: "2^64 - 1",
+                stream_offset: None, jump: false,
+                quality: "linear, weak low bits",
+                big_crush: Unknown, practrand: Fail
+            },
+            xoroshiro_mt_64of128: XoroshiroMt64of128Rng {
+                word_bits: 64, state_bits: 128, period: "2^128 - 1",
+                stream_offset: None, jump: true,
+                quality: "linear state, multiplied output",
+                big_crush: Unknown, practrand: Unknown
+            },
+            xoroshiro_mt_32of128: XoroshiroMt32of128Rng {
+                word_bits: 32, state_bits: 128, period: "2^128 - 1",
+                stream_offset: None, jump: true,
+                quality: "linear state, multiplied output",
+                big_crush: Unknown, practrand: Unknown
+            },
+            xsm32: Xsm32Rng {
+                word_bits: 32, state_bits: 95, period: "2^64",
+                stream_offset: None, jump: false,
+                quality: "counter-based, seekable",
+                big_crush: Unknown, practrand: Pass
+            },
+            xsm64: Xsm64Rng {
+                word_bits: 64, state_bits: 191, period: "2^128",
+                stream_offset: None, jump: false,
+                quality: "counter-based, seekable",
+                big_crush: Unknown, practrand: Pass
+            },
+        }
+    }
+}
+
+/// Result of a test suite, on the output in the native word size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestResult {
+    /// Passes all tests.
+    Pass,
+    /// Fails some tests.
+    Fail,
+    /// Not tested, or no known published result.
+    Unknown,
+}
+
+impl TestResult {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TestResult::Pass => "pass",
+            TestResult::Fail => "fail",
+            TestResult::Unknown => "unknown",
+        }
+    }
+}
+
+/// Description of a generator.
+pub struct Generator {
+    /// Short name, as used on the command line.
+    pub name: &'static str,
+    /// Name of the type.
+    pub type_name: &'static str,
+    /// Size of the seed in bytes.
+    pub seed_bytes: usize,
+    /// Native word size of the output in bits.
+    pub word_bits: u32,
+    /// Size of the state in bits.
+    pub state_bits: u32,
+    /// The period, or its minimum or approximation if the period depends on
+    /// the seed.
+    pub period: &'static str,
+    /// A short note about the design and quality.
+    pub quality: &'static str,
+    /// Result of TestU01 BigCrush.
+    pub big_crush: TestResult,
+    /// Result of PractRand, up to at least 1 TiB.
+    pub practrand: TestResult,
+    /// Byte offset in the seed of a 64-bit stream constant or key, of which
+    /// the lowest bit is always set.
+    pub stream_offset: Option<usize>,
+    /// Create the generator from a seed of `seed_bytes` bytes.
+    ///
+    /// # Panics
+    ///
+    /// If the length of the slice is not `seed_bytes`.
+    pub from_seed: fn(&[u8]) -> Box<dyn RngCore>,
+    /// Create the generator from a seed, and call its jump function the given
+    /// number of times. `None` if the generator has no jump function.
+    pub jumped: Option<JumpedFn>,
+}
+
+/// Create a generator from a seed, and jump ahead the given number of times.
+pub type JumpedFn = fn(&[u8], u32) -> Box<dyn RngCore>;
+
+fn from_seed<R: SeedableRng + RngCore + 'static>(bytes: &[u8]) -> Box<dyn RngCore> {
+    let mut seed = R::Seed::default();
+    seed.as_mut().copy_from_slice(bytes);
+    Box::new(R::from_seed(seed))
+}
+
+/// Generators with a jump function.
+trait Jump {
+    fn jump(&mut self);
+}
+
+macro_rules! impl_jump {
+    ($($ty:ty),*) => { $(
+        impl Jump for $ty {
+            fn jump(&mut self) { <$ty>::jump(self) }
+        }
+    )* }
+}
+
+impl_jump!(Xoroshiro128PlusRng, Xorshift128PlusRng,
+           XoroshiroMt32of128Rng, XoroshiroMt64of128Rng);
+
+fn jumped<R: SeedableRng + RngCore + Jump + 'static>(bytes: &[u8], jumps: u32)
+    -> Box<dyn RngCore>
+{
+    let mut seed = R::Seed::default();
+    seed.as_mut().copy_from_slice(bytes);
+    let mut rng = R::from_seed(seed);
+    for _ in 0..jumps {
+        rng.jump();
+    }
+    Box::new(rng)
+}
+
+macro_rules! jumped {
+    ($ty:ident, true) => { Some(jumped::<$ty>) };
+    ($ty:ident, false) => { None };
+}
+
+macro_rules! registry {
+    ($($name:ident: $ty:ident {
+        word_bits: $word_bits:expr, state_bits: $state_bits:expr,
+        period: $period:expr,
+        stream_offset: $stream_offset:expr, jump: $jump:tt,
+        quality: $quality:expr,
+        big_crush: $big_crush:ident, practrand: $practrand:ident
+    }),* $(,)*) => {
+        /// All seedable generators.
+        pub static GENERATORS: &'static [Generator] = &[$(
+            Generator {
+                name: stringify!($name),
+                type_name: stringify!($ty),
+                seed_bytes: mem::size_of::<<$ty as SeedableRng>::Seed>(),
+                word_bits: $word_bits,
+                state_bits: $state_bits,
+                period: $period,
+                quality: $quality,
+                big_crush: TestResult::$big_crush,
+                practrand: TestResult::$practrand,
+                stream_offset: $stream_offset,
+                from_seed: from_seed::<$ty>,
+                jumped: jumped!($ty, $jump),
+            }
+        ),*];
+    }
+}
+
+generators!(registry);
+
+/// Find a generator by its short name.
+pub fn find(name: &str) -> Option<&'static Generator> {
+    GENERATORS.iter().find(|g| g.name == name)
+}
+
+/// Expand `seed` to a seed of `len` bytes, with SplitMix64.
+///
+/// The outputs of SplitMix64 are written in little-endian order, and the last
+/// one is truncated if `len` is not a multiple of 8.
+pub fn expand_seed(seed: u64, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    seed_policy::fill_splitmix64(seed, &mut bytes);
+    bytes
+}
+
+/// A Markdown table of all generators, as used in the README.
+pub fn markdown_table() -> String {
+    let mut table = String::from(
+        "| Name | Type | Word size | State | Seed | Period | BigCrush | PractRand | Notes |\n\
+         |------|------|----------:|------:|-----:|--------|----------|-----------|-------|\n");
+    for g in GENERATORS {
+        table.push_str(&format!("| `{}` | `{}` | {} | {} | {} | {} | {} | {} | {} |\n",
+                                g.name, g.type_name, g.word_bits,
+                                g.state_bits, g.seed_bytes * 8, g.period,
+                                g.big_crush.as_str(), g.practrand.as_str(),
+                                g.quality));
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use fill::FillWords;
+    use super::*;
+
+    #[test]
+    fn test_all_generators_run() {
+        // Catches arithmetic that overflows in debug builds.
+        for g in GENERATORS {
+            for seed in 0..8 {
+                let mut rng = (g.from_seed)(&expand_seed(seed, g.seed_bytes));
+                let mut bytes = [0u8; 1000];
+                rng.fill_bytes(&mut bytes);
+                for _ in 0..1000 {
+                    rng.next_u32();
+                    rng.next_u64();
+                }
+            }
+        }
+    }
+
+    /// `fill_u32`, `fill_u64` and `fill_u128` must give the same values as
+    /// repeated calls to `next_u32` and `next_u64`, also when the generator is
+    /// in the middle of a buffer.
+    fn check_fill_words<R: FillWords + SeedableRng + Clone>(name: &str) {
+        for skip in 0..9 {
+            let mut rng1 = R::seed_from_u64(skip);
+            for _ in 0..skip {
+                rng1.next_u32();
+            }
+            let mut rng2 = rng1.clone();
+
+            let mut words32 = [0u32; 37];
+            rng1.fill_u32(&mut words32);
+            for &x in words32.iter() {
+                assert_eq!(x, rng2.next_u32(), "fill_u32 of {}", name);
+            }
+
+            let mut words64 = [0u64; 37];
+            rng1.fill_u64(&mut words64);
+            for &x in words64.iter() {
+                assert_eq!(x, rng2.next_u64(), "fill_u64 of {}", name);
+            }
+
+            let mut words128 = [0u128; 37];
+            rng1.fill_u128(&mut words128);
+            for &x in words128.iter() {
+                assert_eq!(x as u64, rng2.next_u64(), "fill_u128 of {}", name);
+                assert_eq!((x >> 64) as u64, rng2.next_u64(), "fill_u128 of {}", name);
+            }
+        }
+    }
+
+    macro_rules! fill_words_tests {
+        ($($name:ident: $ty:ident $props:tt),* $(,)*) => {
+            #[test]
+            fn test_fill_words() {
+                $( check_fill_words::<$ty>(stringify!($name)); )*
+            }
+        }
+    }
+
+    generators!(fill_words_tests);
+
+    /// `from_rng` must give the same generator as `from_seed` with the bytes
+    /// it reads from the source, so both apply the seed policy.
+    fn check_from_rng<R: RngCore + SeedableRng>(name: &str) {
+        for i in 0..8 {
+            let mut source = Sfc64Rng::seed_from_u64(i);
+            let mut seed = R::Seed::default();
+            source.clone().fill_bytes(seed.as_mut());
+            let mut rng1 = R::from_rng(&mut source).unwrap();
+            let mut rng2 = R::from_seed(seed);
+            for _ in 0..16 {
+                assert_eq!(rng1.next_u64(), rng2.next_u64(), "from_rng of {}", name);
+            }
+        }
+    }
+
+    macro_rules! from_rng_tests {
+        ($($name:ident: $ty:ident $props:tt),* $(,)*) => {
+            #[test]
+            fn test_from_rng() {
+                $( check_from_rng::<$ty>(stringify!($name)); )*
+            }
+        }
+    }
+
+    generators!(from_rng_tests);
+}
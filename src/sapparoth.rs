@@ -9,7 +9,10 @@
 
 //! A fast pseudorandom number generator by Ilya Levin.
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
+use core::mem;
 
 /// The Sapparot-2 random number generator by Ilya Levin (32-bit version).
 ///
@@ -31,15 +34,31 @@ impl SeedableRng for Sapparot32Rng {
     type Seed = [u8; 12];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Sapparot32Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::NONE;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 3];
         le::read_u32_into(&seed, &mut seed_u32);
-        Self { a: seed_u32[0],
-               b: seed_u32[1],
-               c: seed_u32[2] }
+        let mut state = Self { a: seed_u32[0],
+                               b: seed_u32[1],
+                               c: seed_u32[2] };
+        for _ in 0..policy.warm_up {
+            state.next_u32();
+        }
+        state
     }
 }
 
-impl Rng for Sapparot32Rng {
+impl RngCore for Sapparot32Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         const PHI: u32 = 0x9e3779b9;
@@ -49,9 +68,7 @@ impl Rng for Sapparot32Rng {
         self.b = self.b.wrapping_add((self.a << 1).wrapping_add(1))
                  ^ self.b.rotate_left(5);
         self.a = self.a.wrapping_add(PHI).rotate_left(7);
-        let m = self.a;
-        self.a = self.b;
-        self.b = m;
+        mem::swap(&mut self.a, &mut self.b);
 
         self.c ^ self.b ^ self.a
     }
@@ -61,20 +78,18 @@ impl Rng for Sapparot32Rng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl FillWords for Sapparot32Rng {}
+
 
 
 /// The Sapparot-2 random number generator by Ilya Levin (64-bit version).
@@ -97,15 +112,31 @@ impl SeedableRng for Sapparot64Rng {
     type Seed = [u8; 24];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Sapparot64Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::NONE;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 3];
         le::read_u64_into(&seed, &mut seed_u64);
-        Self { a: seed_u64[0],
-               b: seed_u64[1],
-               c: seed_u64[2] }
+        let mut state = Self { a: seed_u64[0],
+                               b: seed_u64[1],
+                               c: seed_u64[2] };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
+        state
     }
 }
 
-impl Rng for Sapparot64Rng {
+impl RngCore for Sapparot64Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -120,23 +151,19 @@ impl Rng for Sapparot64Rng {
         self.b = self.b.wrapping_add((self.a << 1).wrapping_add(1))
                  ^ self.b.rotate_left(5);
         self.a = self.a.wrapping_add(PHI).rotate_left(13);
-        let m = self.a;
-        self.a = self.b;
-        self.b = m;
+        mem::swap(&mut self.a, &mut self.b);
 
         self.c ^ self.b ^ self.a
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest)
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
+
+impl FillWords for Sapparot64Rng {}
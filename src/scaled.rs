@@ -0,0 +1,506 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Scaled-down versions of some chaotic RNGs.
+//!
+//! The generators in this module are generic over the word size, and take
+//! their shift and rotate constants at run time. With 8- or 16-bit words the
+//! state is small enough to measure periods exactly, and a statistical test
+//! fails after a lot less output, which makes it possible to compare designs
+//! and sets of constants.
+//!
+//! With 32- or 64-bit words and the default constants, `Jsf` and `Sfc` produce
+//! the same output as `Jsf32Rng`, `Jsf64Rng`, `Sfc32Rng` and `Sfc64Rng` when
+//! those are seeded without warm-up, `Xsm<u32>` the same as `Xsm32Rng`, and
+//! `Gj<u64>` the same as `GjRng`. All word sizes of `Xsm` use the algorithm of
+//! `Xsm32Rng`, so `Xsm<u64>` differs from `Xsm64Rng`.
+//!
+//! The constants for word sizes the authors did not publish are scaled down
+//! from the larger versions. For 8- and 16-bit `Jsf` and `Sfc` they are the
+//! ones of the `jsf8`, `jsf16`, `sfc8` and `sfc16` RNGs from PractRand.
+
+use rand_core::{RngCore, Error};
+use std::fmt;
+use std::hash::Hash;
+use std::ops::{BitOr, BitXor, Shl, Shr};
+use fill;
+
+/// An unsigned integer type that can be used as the word of a scaled RNG.
+pub trait Word: Copy + Eq + Hash + fmt::Debug + fmt::LowerHex
+                + BitOr<Output = Self> + BitXor<Output = Self>
+                + Shl<u32, Output = Self>
+                + Shr<u32, Output = Self> {
+    /// The number of bits.
+    const BITS: u32;
+
+    /// Convert from `u64`, keeping only the low bits.
+    fn from_u64(x: u64) -> Self;
+    fn to_u64(self) -> u64;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    fn wrapping_mul(self, rhs: Self) -> Self;
+    fn rotate_left(self, n: u32) -> Self;
+}
+
+macro_rules! impl_word {
+    ($ty:ty, $bits:expr) => {
+        impl Word for $ty {
+            const BITS: u32 = $bits;
+
+            #[inline]
+            fn from_u64(x: u64) -> Self { x as $ty }
+            #[inline]
+            fn to_u64(self) -> u64 { self as u64 }
+            #[inline]
+            fn wrapping_add(self, rhs: Self) -> Self { self.wrapping_add(rhs) }
+            #[inline]
+            fn wrapping_sub(self, rhs: Self) -> Self { self.wrapping_sub(rhs) }
+            #[inline]
+            fn wrapping_mul(self, rhs: Self) -> Self { self.wrapping_mul(rhs) }
+            #[inline]
+            fn rotate_left(self, n: u32) -> Self { self.rotate_left(n) }
+        }
+    }
+}
+
+impl_word!(u8, 8);
+impl_word!(u16, 16);
+impl_word!(u32, 32);
+impl_word!(u64, 64);
+
+/// Common interface of the scaled RNGs.
+///
+/// Two RNGs compare equal if they have the same constants and the same state,
+/// so they will produce the same output from now on.
+pub trait ScaledRng: Clone + Eq {
+    type Word: Word;
+
+    /// Short name of the algorithm.
+    const NAME: &'static str;
+
+    /// The number of words the seed is split into.
+    const SEED_WORDS: u32;
+
+    /// The number of shift and rotate constants.
+    const SHIFTS: usize;
+
+    /// The default shift and rotate constants for this word size.
+    fn default_shifts() -> Vec<u32>;
+
+    /// Check the number of constants, and that they are smaller than the word
+    /// size.
+    fn validate_shifts(shifts: &[u32]) -> Result<(), String> {
+        check_shifts::<Self::Word>(Self::NAME, shifts, Self::SHIFTS).map(|_| ())
+    }
+
+    /// Create a new RNG with the given constants. The low
+    /// `SEED_WORDS * Word::BITS` bits of `seed` are used to initialize the
+    /// state, the same way as the full-size RNG does it, but without any
+    /// warm-up rounds.
+    ///
+    /// Panics if the constants do not pass `validate_shifts`.
+    fn from_seed(shifts: &[u32], seed: u64) -> Self;
+
+    /// Advance the RNG and return the next word.
+    fn next_word(&mut self) -> Self::Word;
+}
+
+/// Split `seed` into words, starting with the least significant bits.
+fn seed_word<W: Word>(seed: u64, i: u32) -> W {
+    let shift = i * W::BITS;
+    if shift >= 64 { W::from_u64(0) } else { W::from_u64(seed >> shift) }
+}
+
+/// Check the number of constants and their range, and copy them into an array.
+fn check_shifts<W: Word>(name: &str, shifts: &[u32], len: usize)
+    -> Result<[u32; 3], String>
+{
+    if shifts.len() != len {
+        return Err(format!("{} takes {} constants, got {}", name, len, shifts.len()));
+    }
+    let mut result = [0; 3];
+    for (r, &s) in result.iter_mut().zip(shifts.iter()) {
+        if s >= W::BITS {
+            return Err(format!("constant {} is too large for {}-bit words",
+                               s, W::BITS));
+        }
+        *r = s;
+    }
+    Ok(result)
+}
+
+/// `check_shifts`, panicking on an error.
+fn expect_shifts<W: Word>(name: &str, shifts: &[u32], len: usize) -> [u32; 3] {
+    match check_shifts::<W>(name, shifts, len) {
+        Ok(shifts) => shifts,
+        Err(msg) => panic!("{}", msg),
+    }
+}
+
+fn next_u32_via_words<R: ScaledRng>(rng: &mut R) -> u32 {
+    let mut x = 0;
+    let mut bits = 0;
+    while bits < 32 {
+        x |= rng.next_word().to_u64() << bits;
+        bits += R::Word::BITS;
+    }
+    x as u32
+}
+
+fn next_u64_via_words<R: ScaledRng>(rng: &mut R) -> u64 {
+    let mut x = 0;
+    let mut bits = 0;
+    while bits < 64 {
+        x |= rng.next_word().to_u64() << bits;
+        bits += R::Word::BITS;
+    }
+    x
+}
+
+macro_rules! impl_rng {
+    ($name:ident) => {
+        impl<W: Word> RngCore for $name<W> {
+            #[inline]
+            fn next_u32(&mut self) -> u32 {
+                next_u32_via_words(self)
+            }
+
+            #[inline]
+            fn next_u64(&mut self) -> u64 {
+                next_u64_via_words(self)
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                if W::BITS == 64 {
+                    fill::fill_bytes_via_u64(self, dest)
+                } else {
+                    fill::fill_bytes_via_u32(self, dest)
+                }
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+    }
+}
+
+
+
+/// Bob Jenkins' small fast RNG, with any word size.
+///
+/// There are three rotations; a constant of 0 disables the third one, as in
+/// the 32-bit version.
+///
+/// - Default constants: 8 bits `[1, 4, 0]`, 16 bits `[13, 8, 0]`,
+///   32 bits `[27, 17, 0]`, 64 bits `[7, 31, 37]`
+/// - State: 4 words
+/// - Seed: 1 word
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Jsf<W> {
+    a: W,
+    b: W,
+    c: W,
+    d: W,
+    shifts: [u32; 3],
+}
+
+impl<W: Word> ScaledRng for Jsf<W> {
+    type Word = W;
+    const NAME: &'static str = "jsf";
+    const SEED_WORDS: u32 = 1;
+    const SHIFTS: usize = 3;
+
+    fn default_shifts() -> Vec<u32> {
+        match W::BITS {
+            8 => vec![1, 4, 0],
+            16 => vec![13, 8, 0],
+            32 => vec![27, 17, 0],
+            _ => vec![7, 31, 37],
+        }
+    }
+
+    fn from_seed(shifts: &[u32], seed: u64) -> Self {
+        let seed = seed_word(seed, 0);
+        Jsf {
+            a: W::from_u64(0xf1ea5eed), // fleaseed
+            b: seed,
+            c: seed,
+            d: seed,
+            shifts: expect_shifts::<W>(Self::NAME, shifts, Self::SHIFTS),
+        }
+    }
+
+    #[inline]
+    fn next_word(&mut self) -> W {
+        let e = self.a.wrapping_sub(self.b.rotate_left(self.shifts[0]));
+        self.a = self.b ^ self.c.rotate_left(self.shifts[1]);
+        self.b = self.c.wrapping_add(self.d.rotate_left(self.shifts[2]));
+        self.c = self.d.wrapping_add(e);
+        self.d = e.wrapping_add(self.a);
+        self.d
+    }
+}
+
+impl_rng!(Jsf);
+
+
+
+/// Chris Doty-Humphrey's Small Fast Counting RNG, with any word size.
+///
+/// The constants are the barrel shift, right shift and left shift.
+///
+/// - Default constants: 8 bits `[3, 2, 1]`, 16 bits `[6, 5, 3]`,
+///   32 bits `[21, 9, 3]`, 64 bits `[24, 11, 3]`
+/// - State: 4 words, one of which is a counter
+/// - Seed: 3 words
+/// - Period: at least 2<sup>bits</sup>
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Sfc<W> {
+    a: W,
+    b: W,
+    c: W,
+    counter: W,
+    shifts: [u32; 3],
+}
+
+impl<W: Word> ScaledRng for Sfc<W> {
+    type Word = W;
+    const NAME: &'static str = "sfc";
+    const SEED_WORDS: u32 = 3;
+    const SHIFTS: usize = 3;
+
+    fn default_shifts() -> Vec<u32> {
+        match W::BITS {
+            8 => vec![3, 2, 1],
+            16 => vec![6, 5, 3],
+            32 => vec![21, 9, 3],
+            _ => vec![24, 11, 3],
+        }
+    }
+
+    fn from_seed(shifts: &[u32], seed: u64) -> Self {
+        Sfc {
+            a: seed_word(seed, 0),
+            b: seed_word(seed, 1),
+            c: seed_word(seed, 2),
+            counter: W::from_u64(1),
+            shifts: expect_shifts::<W>(Self::NAME, shifts, Self::SHIFTS),
+        }
+    }
+
+    #[inline]
+    fn next_word(&mut self) -> W {
+        let tmp = self.a.wrapping_add(self.b).wrapping_add(self.counter);
+        self.counter = self.counter.wrapping_add(W::from_u64(1));
+        self.a = self.b ^ (self.b >> self.shifts[1]);
+        self.b = self.c.wrapping_add(self.c << self.shifts[2]);
+        self.c = self.c.rotate_left(self.shifts[0]).wrapping_add(tmp);
+        tmp
+    }
+}
+
+impl_rng!(Sfc);
+
+
+
+/// Chris Doty-Humphrey's XSM RNG, with any word size.
+///
+/// The only constant is the rotation of the LCG state. The multiplier is the
+/// one of `Xsm64Rng` for 64-bit words, and the low bits of the one of
+/// `Xsm32Rng` otherwise.
+///
+/// - Default constants: 8 bits `[3]`, 16 bits `[5]`, 32 bits `[11]`,
+///   64 bits `[19]`
+/// - State: 4 words, of which one is always odd
+/// - Seed: 3 words
+/// - Period: 2<sup>2 * bits</sup>
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Xsm<W> {
+    lcg_low: W,
+    lcg_high: W,
+    lcg_adder: W,
+    history: W,
+    shifts: [u32; 3],
+}
+
+impl<W: Word> Xsm<W> {
+    fn multiplier() -> W {
+        if W::BITS == 64 {
+            W::from_u64(0xa3ec647659359acd)
+        } else {
+            W::from_u64(0x6595a395)
+        }
+    }
+}
+
+impl<W: Word> ScaledRng for Xsm<W> {
+    type Word = W;
+    const NAME: &'static str = "xsm";
+    const SEED_WORDS: u32 = 3;
+    const SHIFTS: usize = 1;
+
+    fn default_shifts() -> Vec<u32> {
+        match W::BITS {
+            8 => vec![3],
+            16 => vec![5],
+            32 => vec![11],
+            _ => vec![19],
+        }
+    }
+
+    fn from_seed(shifts: &[u32], seed: u64) -> Self {
+        Xsm {
+            lcg_low: seed_word(seed, 0),
+            lcg_high: seed_word(seed, 1),
+            lcg_adder: seed_word::<W>(seed, 2) | W::from_u64(1),
+            history: W::from_u64(0),
+            shifts: expect_shifts::<W>(Self::NAME, shifts, Self::SHIFTS),
+        }
+    }
+
+    #[inline]
+    fn next_word(&mut self) -> W {
+        let k = Self::multiplier();
+        let half = W::BITS / 2;
+
+        let mut rv = self.history.wrapping_mul(k);
+        let mut tmp = self.lcg_high.wrapping_add(
+            (self.lcg_high ^ self.lcg_low).rotate_left(self.shifts[0]));
+        tmp = tmp.wrapping_mul(k);
+        let mut old_lcg_low = self.lcg_low;
+        self.lcg_low = self.lcg_low.wrapping_add(self.lcg_adder);
+        let carry = (self.lcg_low.to_u64() < self.lcg_adder.to_u64()) as u64;
+        old_lcg_low = old_lcg_low.wrapping_add(W::from_u64(carry));
+        self.lcg_high = self.lcg_high.wrapping_add(old_lcg_low);
+
+        rv = rv ^ (rv >> half);
+        self.history = tmp ^ (tmp >> half);
+        rv.wrapping_add(self.history)
+    }
+}
+
+impl_rng!(Xsm);
+
+
+
+/// Geronimo Jones' small RNG, with any word size.
+///
+/// The constants are the rotations of `c` and `b`; `a` is always rotated by
+/// half a word. The increment of the Weyl sequence is the low bits of the one
+/// of `GjRng`.
+///
+/// - Default constants: 8 bits `[3, 2]`, 16 bits `[6, 5]`, 32 bits `[12, 10]`,
+///   64 bits `[23, 19]`
+/// - State: 4 words, one of which is a Weyl sequence
+/// - Seed: 2 words
+/// - Period: at least 2<sup>bits</sup>
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Gj<W> {
+    a: W,
+    b: W,
+    c: W,
+    d: W,
+    shifts: [u32; 3],
+}
+
+impl<W: Word> ScaledRng for Gj<W> {
+    type Word = W;
+    const NAME: &'static str = "gj";
+    const SEED_WORDS: u32 = 2;
+    const SHIFTS: usize = 2;
+
+    fn default_shifts() -> Vec<u32> {
+        match W::BITS {
+            8 => vec![3, 2],
+            16 => vec![6, 5],
+            32 => vec![12, 10],
+            _ => vec![23, 19],
+        }
+    }
+
+    fn from_seed(shifts: &[u32], seed: u64) -> Self {
+        Gj {
+            a: seed_word(seed, 0),
+            b: seed_word(seed, 1),
+            c: W::from_u64(5000001),
+            d: W::from_u64(0),
+            shifts: expect_shifts::<W>(Self::NAME, shifts, Self::SHIFTS),
+        }
+    }
+
+    #[inline]
+    fn next_word(&mut self) -> W {
+        self.b = self.b.wrapping_add(self.c);
+        self.a = self.a.rotate_left(W::BITS / 2);
+        self.c = self.c ^ self.b;
+
+        self.d = self.d.wrapping_add(W::from_u64(0x55aa96a5));
+
+        self.a = self.a.wrapping_add(self.b);
+        self.c = self.c.rotate_left(self.shifts[0]);
+        self.b = self.b ^ self.a;
+
+        self.a = self.a.wrapping_add(self.c);
+        self.b = self.b.rotate_left(self.shifts[1]);
+        self.c = self.c.wrapping_add(self.a);
+        self.b = self.b.wrapping_add(self.d);
+
+        self.a
+    }
+}
+
+impl_rng!(Gj);
+
+#[cfg(test)]
+mod test {
+    use rand_core::RngCore;
+    use seed_policy::{SeedPolicy, SeedWithPolicy};
+    use {GjRng, Jsf32Rng, Jsf64Rng, Sfc32Rng, Sfc64Rng, Xsm32Rng};
+    use super::*;
+
+    /// Compare the scaled RNG with the full-size one, seeded the same way
+    /// without warm-up.
+    fn check_full_size<S, R, F>(seed: u64, next: F)
+        where S: ScaledRng, R: SeedWithPolicy, F: Fn(&mut R) -> u64
+    {
+        let mut seed_bytes = R::Seed::default();
+        for (i, b) in seed_bytes.as_mut().iter_mut().enumerate().take(8) {
+            *b = (seed >> (i * 8)) as u8;
+        }
+        let mut full = R::from_seed_with_policy(seed_bytes, SeedPolicy::NONE);
+        let mut scaled = S::from_seed(&S::default_shifts(), seed);
+        for _ in 0..100 {
+            assert_eq!(scaled.next_word().to_u64(), next(&mut full),
+                       "{}{}", S::NAME, S::Word::BITS);
+        }
+    }
+
+    #[test]
+    fn test_full_size() {
+        for &seed in [0, 1, 0x0123_4567_89ab_cdef, !0].iter() {
+            check_full_size::<Jsf<u32>, Jsf32Rng, _>(seed, |r| r.next_u32() as u64);
+            check_full_size::<Jsf<u64>, Jsf64Rng, _>(seed, |r| r.next_u64());
+            check_full_size::<Sfc<u32>, Sfc32Rng, _>(seed, |r| r.next_u32() as u64);
+            check_full_size::<Sfc<u64>, Sfc64Rng, _>(seed, |r| r.next_u64());
+            check_full_size::<Xsm<u32>, Xsm32Rng, _>(seed, |r| r.next_u32() as u64);
+            check_full_size::<Gj<u64>, GjRng, _>(seed, |r| r.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_validate_shifts() {
+        assert!(Jsf::<u8>::validate_shifts(&Jsf::<u8>::default_shifts()).is_ok());
+        assert!(Jsf::<u8>::validate_shifts(&[1, 4, 8]).is_err());
+        assert!(Jsf::<u8>::validate_shifts(&[1, 4]).is_err());
+        assert!(Xsm::<u64>::validate_shifts(&[40]).is_ok());
+        assert!(Gj::<u16>::validate_shifts(&[40, 1]).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Warm-up and bad-state handling when seeding.
+//!
+//! Every generator declares the policy `from_seed` uses as
+//! `SeedWithPolicy::SEED_POLICY`. `from_seed_with_policy` can override it, for
+//! example to skip more outputs or to look at the unmodified state.
+//!
+//! The warm-up count is in outputs of the native word size. Checks that are
+//! needed for the generator to work at all, such as forcing the increment of
+//! an LCG to be odd, are always done.
+//!
+//! | Generator               | Warm-up | Bad state check                          |
+//! |-------------------------|--------:|------------------------------------------|
+//! | `CiRng`                 |       0 | zero `t1`, `t2` or `t4` is replaced      |
+//! | `GjRng`                 |      14 | -                                        |
+//! | `Jsf32Rng`              |      20 | -                                        |
+//! | `Jsf64Rng`              |      20 | -                                        |
+//! | `Kiss32Rng`             |       0 | zero xorshift state is replaced          |
+//! | `Kiss64Rng`             |       0 | zero xorshift state is replaced          |
+//! | `MswsRng`               |       0 | zero upper half of the stream is filled  |
+//! | `MwpRng`                |       0 | -                                        |
+//! | `PcgXsh64LcgRng`        |       0 | -                                        |
+//! | `PcgXsl64LcgRng`        |       0 | -                                        |
+//! | `PcgXsl128McgRng`       |       0 | even state is made odd                   |
+//! | `Sapparot32Rng`         |       0 | -                                        |
+//! | `Sapparot64Rng`         |       0 | -                                        |
+//! | `Sfc32Rng`              |      15 | -                                        |
+//! | `Sfc64Rng`              |      18 | -                                        |
+//! | `Velox3bRng`            |      16 | -                                        |
+//! | `Xoroshiro128PlusRng`   |       0 | all-zero state is replaced               |
+//! | `Xoroshiro64PlusRng`    |       0 | all-zero state is replaced               |
+//! | `XoroshiroMt32of128Rng` |       0 | all-zero state is replaced               |
+//! | `XoroshiroMt64of128Rng` |       0 | all-zero state is replaced               |
+//! | `Xorshift128_32Rng`     |       0 | all-zero state is replaced               |
+//! | `Xorshift128_64Rng`     |       0 | all-zero state is replaced               |
+//! | `Xorshift128PlusRng`    |       0 | all-zero state is replaced               |
+//! | `XorshiftMt32Rng`       |       0 | all-zero state is replaced               |
+//! | `XorshiftMt64Rng`       |       0 | all-zero state is replaced               |
+//! | `Xsm32Rng`              |       1 | -                                        |
+//! | `Xsm64Rng`              |       1 | -                                        |
+//!
+//! `SeedableRng::seed_from_u64` expands the `u64` to a full seed with
+//! SplitMix64, and then uses `from_seed`. The tools use the same expansion for
+//! their `--seed` option, so a generator created with `seed_from_u64(s)` gives
+//! the same output as the tools with `--seed s`.
+
+use rand_core::SeedableRng;
+
+/// The word that replaces zero words of a seed that would give a bad state.
+#[allow(clippy::unusual_byte_groupings)] // "odd biases bad seed"
+pub const BAD_SEED_REPLACEMENT: u64 = 0x0DD_B1A5E5_BAD_5EED;
+
+/// How a generator turns a seed into its initial state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeedPolicy {
+    /// Number of outputs to discard after seeding.
+    pub warm_up: u32,
+    /// Replace seeds that would put the generator in a bad state, such as the
+    /// all-zero state of a linear generator.
+    pub check_bad_state: bool,
+}
+
+impl SeedPolicy {
+    /// Use the seed as-is: no warm-up, and no check for bad states.
+    pub const NONE: SeedPolicy = SeedPolicy { warm_up: 0, check_bad_state: false };
+
+    /// Only check for bad states.
+    pub const CHECK: SeedPolicy = SeedPolicy { warm_up: 0, check_bad_state: true };
+
+    /// Only discard the first `warm_up` outputs.
+    pub fn warm_up(warm_up: u32) -> SeedPolicy {
+        SeedPolicy { warm_up, check_bad_state: false }
+    }
+}
+
+/// A generator with a configurable seeding policy.
+pub trait SeedWithPolicy: SeedableRng {
+    /// The policy used by `from_seed`.
+    const SEED_POLICY: SeedPolicy;
+
+    /// Create a new generator from `seed`, using `policy` instead of the
+    /// default `SEED_POLICY`.
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self;
+}
+
+/// The increment of the state of SplitMix64.
+pub const SPLITMIX64_GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+/// The output function of SplitMix64, a bijection.
+#[inline]
+pub fn splitmix64_mix(z: u64) -> u64 {
+    let z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Fill `dest` with the outputs of SplitMix64 started from `state`, in
+/// little-endian order.
+pub fn fill_splitmix64(state: u64, dest: &mut [u8]) {
+    let mut state = state;
+    for chunk in dest.chunks_mut(8) {
+        state = state.wrapping_add(SPLITMIX64_GAMMA);
+        let z = splitmix64_mix(state);
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = (z >> (i * 8)) as u8;
+        }
+    }
+}
+
+/// The `seed_from_u64` of all generators.
+pub fn seed_from_u64<R: SeedableRng>(state: u64) -> R {
+    let mut seed = R::Seed::default();
+    fill_splitmix64(state, seed.as_mut());
+    R::from_seed(seed)
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use {Kiss32Rng, Kiss64Rng, PcgXsl128McgRng, Xoroshiro128PlusRng,
+         Xoroshiro64PlusRng, XoroshiroMt32of128Rng, XoroshiroMt64of128Rng,
+         Xorshift128_32Rng, Xorshift128_64Rng, Xorshift128PlusRng,
+         XorshiftMt32Rng, XorshiftMt64Rng};
+    use super::*;
+
+    fn is_stuck<R: RngCore>(rng: &mut R) -> bool {
+        (0..16).all(|_| rng.next_u64() == 0)
+    }
+
+    fn check_zero_seed<R: SeedWithPolicy + RngCore>(name: &str) {
+        let zero = R::Seed::default;
+        assert!(R::SEED_POLICY.check_bad_state, "{}", name);
+        assert!(is_stuck(&mut R::from_seed_with_policy(zero(),
+                                                       SeedPolicy::NONE)),
+                "{} with an unchecked all-zero seed", name);
+        assert!(!is_stuck(&mut R::from_seed(zero())),
+                "{} with an all-zero seed", name);
+        assert!(!is_stuck(&mut R::from_seed_with_policy(zero(),
+                                                        SeedPolicy::CHECK)),
+                "{} with an all-zero seed", name);
+    }
+
+    #[test]
+    fn test_check_bad_state_zero_seed() {
+        check_zero_seed::<Xoroshiro128PlusRng>("Xoroshiro128PlusRng");
+        check_zero_seed::<Xoroshiro64PlusRng>("Xoroshiro64PlusRng");
+        check_zero_seed::<XoroshiroMt32of128Rng>("XoroshiroMt32of128Rng");
+        check_zero_seed::<XoroshiroMt64of128Rng>("XoroshiroMt64of128Rng");
+        check_zero_seed::<Xorshift128_32Rng>("Xorshift128_32Rng");
+        check_zero_seed::<Xorshift128_64Rng>("Xorshift128_64Rng");
+        check_zero_seed::<Xorshift128PlusRng>("Xorshift128PlusRng");
+        check_zero_seed::<XorshiftMt32Rng>("XorshiftMt32Rng");
+        check_zero_seed::<XorshiftMt64Rng>("XorshiftMt64Rng");
+        check_zero_seed::<PcgXsl128McgRng>("PcgXsl128McgRng");
+    }
+
+    #[test]
+    fn test_check_bad_state_kiss() {
+        // Only the xorshift part of KISS can get stuck. Compare against the
+        // same seed with a non-zero xorshift state filled in by hand.
+        let mut rng1 = Kiss32Rng::from_seed([0u8; 16]);
+        let mut rng2 = Kiss32Rng::from_seed_with_policy([0u8; 16],
+                                                        SeedPolicy::NONE);
+        let outputs1: Vec<u32> = (0..16).map(|_| rng1.next_u32()).collect();
+        let outputs2: Vec<u32> = (0..16).map(|_| rng2.next_u32()).collect();
+        assert!(outputs1 != outputs2);
+
+        let mut rng1 = Kiss64Rng::from_seed([0u8; 32]);
+        let mut rng2 = Kiss64Rng::from_seed_with_policy([0u8; 32],
+                                                        SeedPolicy::NONE);
+        let outputs1: Vec<u64> = (0..16).map(|_| rng1.next_u64()).collect();
+        let outputs2: Vec<u64> = (0..16).map(|_| rng2.next_u64()).collect();
+        assert!(outputs1 != outputs2);
+    }
+
+    #[test]
+    fn test_pcg_mcg_odd_state() {
+        // The lowest bit of the state is the lowest bit of the second word.
+        let mut seed = [0u8; 16];
+        fill_splitmix64(1, &mut seed);
+
+        seed[8] |= 1;
+        let mut rng1 = PcgXsl128McgRng::from_seed(seed);
+        let mut rng2 = PcgXsl128McgRng::from_seed_with_policy(seed,
+                                                              SeedPolicy::NONE);
+        for _ in 0..16 {
+            assert_eq!(rng1.next_u64(), rng2.next_u64());
+        }
+
+        let odd_seed = seed;
+        seed[8] &= !1;
+        let mut rng1 = PcgXsl128McgRng::from_seed(seed);
+        let mut rng2 = PcgXsl128McgRng::from_seed_with_policy(seed,
+                                                              SeedPolicy::NONE);
+        let mut rng3 = PcgXsl128McgRng::from_seed_with_policy(odd_seed,
+                                                              SeedPolicy::NONE);
+        let mut differs = false;
+        for _ in 0..16 {
+            let x = rng1.next_u64();
+            differs |= x != rng2.next_u64();
+            assert_eq!(x, rng3.next_u64());
+        }
+        assert!(differs);
+    }
+}
@@ -9,8 +9,9 @@
 
 //! A Small Fast Counting RNG, version 4.
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
-use core::slice;
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords, LaneBlocks};
 
 /// A Small Fast Counting RNG designed by Chris Doty-Humphrey (32-bit version).
 ///
@@ -34,35 +35,34 @@ impl SeedableRng for Sfc32Rng {
     type Seed = [u8; 12];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Sfc32Rng {
+    // Skip the first 15 outputs, just in case we have a bad seed.
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 15,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 3];
         le::read_u32_into(&seed, &mut seed_u32);
         let mut state = Self { a: seed_u32[0],
                                b: seed_u32[1],
                                c: seed_u32[2],
                                counter: 1};
-        // Skip the first 15 outputs, just in case we have a bad seed.
-        for _ in 0..15 {
+        for _ in 0..policy.warm_up {
             state.next_u32();
         }
         state
     }
-
-    fn from_rng<R: Rng>(mut rng: R) -> Result<Self, Error> {
-        // Custom `from_rng` function. Because we can assume the seed to be of
-        // good quality, it is not neccesary to discard the first couple of
-        // rounds.
-        let mut seed_u32 = [0u32; 3];
-        unsafe {
-            let ptr = seed_u32.as_mut_ptr() as *mut u8;
-
-            let slice = slice::from_raw_parts_mut(ptr, 4*3);
-            rng.try_fill(slice)?;
-        }
-        Ok(Self { a: seed_u32[0], b: seed_u32[1], c: seed_u32[2], counter: 1 })
-    }
 }
 
-impl Rng for Sfc32Rng {
+impl RngCore for Sfc32Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         // good sets include {21,9,3} and {15,8,3}
@@ -71,7 +71,7 @@ impl Rng for Sfc32Rng {
         const LSHIFT: u32 = 3;
 
         let tmp = self.a.wrapping_add(self.b).wrapping_add(self.counter);
-        self.counter += 1;
+        self.counter = self.counter.wrapping_add(1);
         self.a = self.b ^ (self.b >> RSHIFT);
         self.b = self.c.wrapping_add(self.c << LSHIFT);
         self.c = self.c.rotate_left(BARREL_SHIFT).wrapping_add(tmp);
@@ -83,17 +83,38 @@ impl Rng for Sfc32Rng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
+}
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+impl FillWords for Sfc32Rng {
+    fn fill_u32(&mut self, dest: &mut [u32]) {
+        // Same as `next_u32`, but with the state kept in local variables
+        // for the whole loop.
+        const BARREL_SHIFT: u32 = 21;
+        const RSHIFT: u32 = 9;
+        const LSHIFT: u32 = 3;
+
+        let (mut a, mut b, mut c, mut counter) =
+            (self.a, self.b, self.c, self.counter);
+        for x in dest.iter_mut() {
+            let tmp = a.wrapping_add(b).wrapping_add(counter);
+            counter = counter.wrapping_add(1);
+            a = b ^ (b >> RSHIFT);
+            b = c.wrapping_add(c << LSHIFT);
+            c = c.rotate_left(BARREL_SHIFT).wrapping_add(tmp);
+            *x = tmp;
+        }
+        self.a = a;
+        self.b = b;
+        self.c = c;
+        self.counter = counter;
     }
 }
 
@@ -123,35 +144,34 @@ impl SeedableRng for Sfc64Rng {
     type Seed = [u8; 24];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Sfc64Rng {
+    // Skip the first 18 outputs, just in case we have a bad seed.
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 18,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 3];
         le::read_u64_into(&seed, &mut seed_u64);
         let mut state = Self { a: seed_u64[0],
                                b: seed_u64[1],
                                c: seed_u64[2],
                                counter: 1};
-        // Skip the first 18 outputs, just in case we have a bad seed.
-        for _ in 0..18 {
+        for _ in 0..policy.warm_up {
             state.next_u64();
         }
         state
     }
-
-    fn from_rng<R: Rng>(mut rng: R) -> Result<Self, Error> {
-        // Custom `from_rng` function. Because we can assume the seed to be of
-        // good quality, it is not neccesary to discard the first couple of
-        // rounds.
-        let mut seed_u64 = [0u64; 3];
-        unsafe {
-            let ptr = seed_u64.as_mut_ptr() as *mut u8;
-
-            let slice = slice::from_raw_parts_mut(ptr, 8*3);
-            rng.try_fill(slice)?;
-        }
-        Ok(Self { a: seed_u64[0], b: seed_u64[1], c: seed_u64[2], counter: 1 })
-    }
 }
 
-impl Rng for Sfc64Rng {
+impl RngCore for Sfc64Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -165,23 +185,224 @@ impl Rng for Sfc64Rng {
         const LSHIFT: u32 = 3;
 
         let tmp = self.a.wrapping_add(self.b).wrapping_add(self.counter);
-        self.counter += 1;
+        self.counter = self.counter.wrapping_add(1);
         self.a = self.b ^ (self.b >> RSHIFT);
         self.b = self.c.wrapping_add(self.c << LSHIFT);
         self.c = self.c.rotate_left(BARREL_SHIFT).wrapping_add(tmp);
         tmp
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u64(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Sfc64Rng {}
+
+
+
+/// Four independent `Sfc64Rng` streams, advanced in lock-step.
+///
+/// The state is stored as one array per variable, so the compiler can keep all
+/// four lanes in vector registers. Lane `i` produces exactly the same outputs
+/// as the `Sfc64Rng` it was constructed from.
+///
+/// The `RngCore` methods return lane 0, 1, 2 and 3 of every step in turn.
+///
+/// The seed is the seeds of the four lanes, one after the other.
+#[derive(Clone)]
+pub struct Sfc64x4Rng {
+    a: [u64; 4],
+    b: [u64; 4],
+    c: [u64; 4],
+    counter: [u64; 4],
+    results: [u64; 4],
+    index: usize,
+}
+
+impl Sfc64x4Rng {
+    /// Combine four scalar generators into one multi-lane generator.
+    pub fn from_lanes(lanes: [Sfc64Rng; 4]) -> Self {
+        let mut state = Self { a: [0; 4], b: [0; 4], c: [0; 4], counter: [0; 4],
+                               results: [0; 4], index: 4 };
+        for (i, lane) in lanes.iter().enumerate() {
+            state.a[i] = lane.a;
+            state.b[i] = lane.b;
+            state.c[i] = lane.c;
+            state.counter[i] = lane.counter;
+        }
+        state
+    }
+
+    /// Advance all lanes by one step, and return the output of each lane.
+    #[inline]
+    pub fn next_u64x4(&mut self) -> [u64; 4] {
+        const BARREL_SHIFT: u32 = 24;
+        const RSHIFT: u32 = 11;
+        const LSHIFT: u32 = 3;
+
+        let mut tmp = [0u64; 4];
+        for (i, out) in tmp.iter_mut().enumerate() {
+            *out = self.a[i].wrapping_add(self.b[i])
+                            .wrapping_add(self.counter[i]);
+            self.counter[i] = self.counter[i].wrapping_add(1);
+            self.a[i] = self.b[i] ^ (self.b[i] >> RSHIFT);
+            self.b[i] = self.c[i].wrapping_add(self.c[i] << LSHIFT);
+            self.c[i] = self.c[i].rotate_left(BARREL_SHIFT).wrapping_add(*out);
+        }
+        tmp
+    }
+}
+
+/// The seed of `Sfc64x4Rng`: the seeds of the four `Sfc64Rng` lanes.
+#[derive(Clone)]
+pub struct Sfc64x4Seed(pub [u8; 96]);
+
+impl Default for Sfc64x4Seed {
+    fn default() -> Self {
+        Sfc64x4Seed([0; 96])
+    }
+}
+
+impl AsMut<[u8]> for Sfc64x4Seed {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl SeedableRng for Sfc64x4Rng {
+    type Seed = Sfc64x4Seed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let lane = |i: usize| {
+            let mut lane_seed = [0u8; 24];
+            lane_seed.copy_from_slice(&seed.0[i * 24..(i + 1) * 24]);
+            Sfc64Rng::from_seed(lane_seed)
+        };
+        Self::from_lanes([lane(0), lane(1), lane(2), lane(3)])
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl LaneBlocks for Sfc64x4Rng {
+    type Word = u64;
+    type Block = [u64; 4];
+
+    #[inline]
+    fn next_block(&mut self) -> [u64; 4] {
+        self.next_u64x4()
+    }
+
+    #[inline]
+    fn buffer(&mut self) -> (&mut [u64; 4], &mut usize) {
+        (&mut self.results, &mut self.index)
+    }
+}
+
+impl RngCore for Sfc64x4Rng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        fill::next_word_via_blocks(self)
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest)
+        fill::fill_bytes_via_blocks(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Sfc64x4Rng {
+    fn fill_u64(&mut self, dest: &mut [u64]) {
+        fill::fill_words_via_blocks(self, dest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use fill::{self, FillWords};
+    use super::*;
+
+    #[test]
+    fn test_sfc_seed_endianness() {
+        // The seed is read as little-endian words on every target. Check
+        // this by laying out the same words both ways: only the
+        // little-endian layout may give the state built from the words.
+        let words32 = [0x0102_0304u32, 0x0506_0708, 0x090a_0b0c];
+        let check32 = |le: bool, a: u32, b: u32, c: u32| {
+            let mut seed = [0u8; 12];
+            for (chunk, &w) in seed.chunks_mut(4).zip(words32.iter()) {
+                let bytes = if le { w.to_le_bytes() } else { w.to_be_bytes() };
+                chunk.copy_from_slice(&bytes);
+            }
+            let mut rng1 = Sfc32Rng::from_seed(seed);
+            let mut rng2 = Sfc32Rng { a, b, c, counter: 1 };
+            for _ in 0..Sfc32Rng::SEED_POLICY.warm_up {
+                rng2.next_u32();
+            }
+            assert_eq!(rng1.next_u32(), rng2.next_u32());
+        };
+        check32(true, words32[0], words32[1], words32[2]);
+        check32(false, words32[0].swap_bytes(), words32[1].swap_bytes(),
+                words32[2].swap_bytes());
+
+        let words64 = [0x0102_0304_0506_0708u64, 0x090a_0b0c_0d0e_0f10,
+                       0x1112_1314_1516_1718];
+        let check64 = |le: bool, a: u64, b: u64, c: u64| {
+            let mut seed = [0u8; 24];
+            for (chunk, &w) in seed.chunks_mut(8).zip(words64.iter()) {
+                let bytes = if le { w.to_le_bytes() } else { w.to_be_bytes() };
+                chunk.copy_from_slice(&bytes);
+            }
+            let mut rng1 = Sfc64Rng::from_seed(seed);
+            let mut rng2 = Sfc64Rng { a, b, c, counter: 1 };
+            for _ in 0..Sfc64Rng::SEED_POLICY.warm_up {
+                rng2.next_u64();
+            }
+            assert_eq!(rng1.next_u64(), rng2.next_u64());
+        };
+        check64(true, words64[0], words64[1], words64[2]);
+        check64(false, words64[0].swap_bytes(), words64[1].swap_bytes(),
+                words64[2].swap_bytes());
+    }
+
+    #[test]
+    fn test_sfc32_counter_wraps() {
+        let mut rng1 = Sfc32Rng { a: 1, b: 2, c: 3, counter: !0 - 2 };
+        let mut rng2 = rng1.clone();
+        let mut words = [0u32; 8];
+        rng1.fill_u32(&mut words);
+        for &x in words.iter() {
+            assert_eq!(x, rng2.next_u32());
+        }
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    #[test]
+    fn test_sfc64x4_lanes() {
+        let mut seed = [0u8; 96];
+        fill::lanes_test_seed(&mut seed);
+        let lanes: Vec<Sfc64Rng> = seed.chunks(24).map(|chunk| {
+            let mut lane_seed = [0u8; 24];
+            lane_seed.copy_from_slice(chunk);
+            Sfc64Rng::from_seed(lane_seed)
+        }).collect();
+        fill::check_lanes(Sfc64x4Rng::from_seed(Sfc64x4Seed(seed)), lanes, |lane| lane.next_u64());
     }
 }
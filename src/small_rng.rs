@@ -0,0 +1,177 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A fast, high-quality generator for the target, chosen at compile time.
+
+use rand_core::{RngCore, SeedableRng, Error, le};
+use seed_policy;
+use fill::FillWords;
+use sfc;
+
+#[cfg(target_pointer_width = "64")]
+type Inner = sfc::Sfc64Rng;
+#[cfg(not(target_pointer_width = "64"))]
+type Inner = sfc::Sfc32Rng;
+
+/// The fastest high-quality generator for the target.
+///
+/// On targets with 64-bit pointers this is `Sfc64Rng`, and on all others
+/// `Sfc32Rng`: the 64-bit version is faster where 64-bit arithmetic is
+/// native, and the 32-bit version where it has to be emulated. Both pass
+/// BigCrush and PractRand.
+///
+/// The seed is 32 bytes on every target. It is mixed into the seed of the
+/// underlying generator with the output function of SplitMix64, so every byte
+/// of it affects all of the state.
+///
+/// # Reproducibility
+///
+/// `SmallRng` produces the same output from the same seed on all targets with
+/// the same pointer width, independent of their endianness. All 64-bit targets
+/// share one output stream, and all 32- and 16-bit targets share another; the
+/// two are different.
+///
+/// Which generator is used may change in a later version of this crate. Use
+/// `Sfc64Rng` or `Sfc32Rng` directly if the output has to be the same
+/// everywhere, or stay the same between versions.
+///
+/// Like the other generators, `SmallRng` is not cryptographically secure and
+/// does not implement `CryptoRng`.
+#[derive(Clone)]
+pub struct SmallRng(Inner);
+
+impl SeedableRng for SmallRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut inner_seed = <Inner as SeedableRng>::Seed::default();
+        mix_seed(&seed, inner_seed.as_mut());
+        SmallRng(Inner::from_seed(inner_seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+/// Mix the four words of `seed` into every word of `dest`.
+///
+/// Every word of `dest` is the xor of the SplitMix64 output function applied
+/// to each seed word, each time with the next state of SplitMix64 added.
+/// A 12-byte `dest` gets the first 12 bytes of a 24-byte one.
+fn mix_seed(seed: &[u8; 32], dest: &mut [u8]) {
+    let mut words = [0u64; 4];
+    le::read_u64_into(seed, &mut words);
+    let mut state = 0u64;
+    for chunk in dest.chunks_mut(8) {
+        let mut x = 0;
+        for &w in words.iter() {
+            state = state.wrapping_add(seed_policy::SPLITMIX64_GAMMA);
+            x ^= seed_policy::splitmix64_mix(state ^ w);
+        }
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = (x >> (i * 8)) as u8;
+        }
+    }
+}
+
+impl RngCore for SmallRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl FillWords for SmallRng {
+    fn fill_u32(&mut self, dest: &mut [u32]) {
+        self.0.fill_u32(dest)
+    }
+
+    fn fill_u64(&mut self, dest: &mut [u64]) {
+        self.0.fill_u64(dest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use super::*;
+
+    fn test_seed() -> [u8; 32] {
+        let mut seed = [0u8; 32];
+        for (i, b) in seed.iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        seed
+    }
+
+    // The output is only fixed per pointer width, see the documentation of
+    // `SmallRng`.
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_small_rng_output() {
+        let mut inner = [0u8; 24];
+        mix_seed(&test_seed(), &mut inner);
+        let mut rng = SmallRng::from_seed(test_seed());
+        let mut sfc = sfc::Sfc64Rng::from_seed(inner);
+        let expected = [0x7bee95ba5b31e8d9, 0x9e004f3d13bc1fc3,
+                        0xbd55404cab796176, 0x5a2b10619a72ca99];
+        for &e in expected.iter() {
+            let x = rng.next_u64();
+            assert_eq!(x, sfc.next_u64());
+            assert_eq!(x, e);
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_pointer_width = "64"))]
+    fn test_small_rng_output() {
+        let mut inner = [0u8; 12];
+        mix_seed(&test_seed(), &mut inner);
+        let mut rng = SmallRng::from_seed(test_seed());
+        let mut sfc = sfc::Sfc32Rng::from_seed(inner);
+        let expected = [0x4c2c4ea8, 0x5be5495b, 0xd62b21de, 0xcb156627];
+        for &e in expected.iter() {
+            let x = rng.next_u32();
+            assert_eq!(x, sfc.next_u32());
+            assert_eq!(x, e);
+        }
+    }
+
+    #[test]
+    fn test_small_rng_seed_collisions() {
+        // Seeds that differ by the same byte at two offsets that are a
+        // multiple of the size of the inner seed apart, 24 or 12 bytes, would
+        // collide if the seed was folded with xor.
+        let first_words = |seed| {
+            let mut rng = SmallRng::from_seed(seed);
+            [rng.next_u64(), rng.next_u64()]
+        };
+        let zero = first_words([0u8; 32]);
+        for &offset in [12, 24].iter() {
+            let mut seed = [0u8; 32];
+            seed[0] = 0x5a;
+            seed[offset] = 0x5a;
+            assert!(first_words(seed) != zero, "offset {}", offset);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Special functions to turn test statistics into p-values.
+//!
+//! The incomplete gamma functions follow the series and continued fraction of
+//! Numerical Recipes, chapter 6.2.
+
+use std::f64;
+use std::f64::consts::PI;
+
+const EPS: f64 = 1e-15;
+const TINY: f64 = 1e-300;
+const MAX_ITER: usize = 100_000;
+
+/// Natural logarithm of the gamma function, using the Lanczos approximation.
+pub fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    // The published coefficients, with more digits than an f64 holds.
+    #[allow(clippy::excessive_precision)]
+    const COEF: [f64; 9] = [0.99999999999980993, 676.5203681218851,
+                            -1259.1392167224028, 771.32342877765313,
+                            -176.61502916214059, 12.507343278686905,
+                            -0.13857109526572012, 9.9843695780195716e-6,
+                            1.5056327351493116e-7];
+    if x < 0.5 {
+        // Reflection formula
+        (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x)
+    } else {
+        let x = x - 1.0;
+        let mut a = COEF[0];
+        for (i, c) in COEF.iter().enumerate().skip(1) {
+            a += c / (x + i as f64);
+        }
+        let t = x + G + 0.5;
+        0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+    }
+}
+
+/// `ln(x^a e^-x / gamma(a))`, the common factor of both incomplete gamma
+/// functions.
+fn gamma_prefactor(a: f64, x: f64) -> f64 {
+    a * x.ln() - x - ln_gamma(a)
+}
+
+/// Regularized lower incomplete gamma function P(a, x), by its series.
+fn gamma_p_series(a: f64, x: f64) -> f64 {
+    let mut ap = a;
+    let mut del = 1.0 / a;
+    let mut sum = del;
+    for _ in 0..MAX_ITER {
+        ap += 1.0;
+        del *= x / ap;
+        sum += del;
+        if del.abs() < sum.abs() * EPS {
+            break;
+        }
+    }
+    sum * gamma_prefactor(a, x).exp()
+}
+
+/// Regularized upper incomplete gamma function Q(a, x), by its continued
+/// fraction (modified Lentz's method).
+fn gamma_q_fraction(a: f64, x: f64) -> f64 {
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..MAX_ITER {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY { d = TINY; }
+        c = b + an / c;
+        if c.abs() < TINY { c = TINY; }
+        d = 1.0 / d;
+        let del = d * c;
+        h *= del;
+        if (del - 1.0).abs() < EPS {
+            break;
+        }
+    }
+    gamma_prefactor(a, x).exp() * h
+}
+
+/// Regularized upper incomplete gamma function Q(a, x) = 1 - P(a, x).
+pub fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        1.0
+    } else if x < a + 1.0 {
+        1.0 - gamma_p_series(a, x)
+    } else {
+        gamma_q_fraction(a, x)
+    }
+}
+
+/// Complementary error function.
+pub fn erfc(x: f64) -> f64 {
+    if x >= 0.0 {
+        gamma_q(0.5, x * x)
+    } else {
+        2.0 - gamma_q(0.5, x * x)
+    }
+}
+
+/// Two-sided p-value of a standard normal statistic.
+pub fn normal_p(z: f64) -> f64 {
+    erfc(z.abs() / f64::consts::SQRT_2)
+}
+
+/// p-value of a chi-square statistic with `df` degrees of freedom, i.e. the
+/// probability of a statistic at least as large.
+pub fn chi_square_p(x: f64, df: f64) -> f64 {
+    gamma_q(df / 2.0, x / 2.0)
+}
+
+/// The chi-square statistic of `observed` counts against `expected` counts.
+pub fn chi_square(observed: &[u64], expected: &[f64]) -> f64 {
+    observed.iter().zip(expected.iter()).map(|(&o, &e)| {
+        let diff = o as f64 - e;
+        diff * diff / e
+    }).sum()
+}
+
+/// The mid-p value of a Poisson distributed count `k` with mean `lambda`:
+/// P(X < k) + P(X = k) / 2. Like the p-values of the continuous tests it is
+/// close to uniform, so both tails can be evaluated the same way.
+pub fn poisson_p(k: u64, lambda: f64) -> f64 {
+    // P(X < k) = Q(k, lambda)
+    let below = if k == 0 { 0.0 } else { gamma_q(k as f64, lambda) };
+    let k = k as f64;
+    let equal = (k * lambda.ln() - lambda - ln_gamma(k + 1.0)).exp();
+    below + equal / 2.0
+}
+
+/// Probability of exactly `k` successes out of `n` trials with probability
+/// one half.
+pub fn binomial_half_pmf(n: u64, k: u64) -> f64 {
+    let n_f = n as f64;
+    let k_f = k as f64;
+    (ln_gamma(n_f + 1.0) - ln_gamma(k_f + 1.0) - ln_gamma(n_f - k_f + 1.0)
+        - n_f * 2f64.ln()).exp()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(x: f64, expected: f64) {
+        assert!((x - expected).abs() <= 1e-12 * expected.abs(),
+                "{} != {}", x, expected);
+    }
+
+    #[test]
+    fn test_erfc() {
+        assert_close(erfc(0.0), 1.0);
+        assert_close(erfc(0.5), 0.4795001221869535);
+        assert_close(erfc(2.0), 0.004677734981047265);
+        assert_close(erfc(-1.0), 1.842700792949715);
+    }
+
+    #[test]
+    fn test_gamma_q() {
+        // Q(1, x) = e^-x
+        assert_close(gamma_q(1.0, 2.0), (-2.0f64).exp());
+        // By the series, and by the continued fraction.
+        assert_close(gamma_q(3.0, 2.5), 0.5438131158833296);
+        assert_close(gamma_q(10.0, 15.0), 0.06985366069940976);
+        assert_close(gamma_q(0.5, 0.3), 0.4385780260809999);
+        assert_close(gamma_q(2.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_chi_square_p() {
+        assert_close(chi_square_p(10.0, 2.0), 0.006737946999085467);
+        // The critical values of the 5% level.
+        assert_close(chi_square_p(3.841458820694124, 1.0), 0.05);
+        assert_close(chi_square_p(9.487729036781154, 4.0), 0.05);
+    }
+
+    #[test]
+    fn test_poisson_p() {
+        // P(X = 0) / 2
+        assert_close(poisson_p(0, 1.0), 0.18393972058572117);
+        // P(X < 2) + P(X = 2) / 2
+        assert_close(poisson_p(2, 2.0), 0.5413411329464508);
+    }
+
+    #[test]
+    fn test_binomial_half_pmf() {
+        assert_close(binomial_half_pmf(4, 2), 0.375);
+        assert_close(binomial_half_pmf(10, 5), 0.24609375);
+        assert_close(binomial_half_pmf(64, 32), 0.09934675374796689);
+        assert_close(binomial_half_pmf(8, 0), 1.0 / 256.0);
+    }
+}
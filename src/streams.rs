@@ -0,0 +1,265 @@
+// Copyright 2017 Paul Dicker.
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Multiple instances of a generator, as used for parallel streams.
+//!
+//! There are several common ways to seed the instances of a generator that are
+//! given to worker threads. Correlations between the instances do not show up
+//! when testing a single instance, but they do when the outputs of the
+//! instances are interleaved into one stream.
+
+use rand_core::RngCore;
+use registry::{self, Generator};
+
+/// How to seed the instances.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// The seed plus 0, 1, .., K-1, read as a little-endian integer.
+    Adjacent,
+    /// Successive seeds from one SplitMix64 sequence, started from the first
+    /// 8 bytes of the seed.
+    SplitMix,
+    /// The same seed, with the stream constant or key of the RNG set to the
+    /// next K odd constants.
+    Stream,
+    /// The same seed, with every instance jumped ahead once more than the
+    /// previous.
+    Jump,
+}
+
+impl Strategy {
+    /// All strategies.
+    pub const ALL: [Strategy; 4] =
+        [Strategy::Adjacent, Strategy::SplitMix, Strategy::Stream, Strategy::Jump];
+
+    pub fn parse(s: &str) -> Option<Strategy> {
+        Strategy::ALL.iter().cloned().find(|strategy| strategy.as_str() == s)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Strategy::Adjacent => "adjacent",
+            Strategy::SplitMix => "splitmix",
+            Strategy::Stream => "stream",
+            Strategy::Jump => "jump",
+        }
+    }
+
+    /// Whether the strategy can be used with a generator.
+    pub fn supports(&self, generator: &Generator) -> bool {
+        match *self {
+            Strategy::Stream => generator.stream_offset.is_some(),
+            Strategy::Jump => generator.jumped.is_some(),
+            _ => true,
+        }
+    }
+}
+
+/// Create `streams` instances of a generator from `seed`.
+///
+/// # Errors
+///
+/// If the generator has no stream constant or jump function for the
+/// strategy.
+pub fn make_rngs(generator: &Generator, seed: &[u8], streams: u32,
+                 strategy: Strategy)
+    -> Result<Vec<Box<dyn RngCore>>, String>
+{
+    let mut rngs = Vec::new();
+    let seed_bytes = generator.seed_bytes;
+    let splitmix = if strategy == Strategy::SplitMix {
+        let base = seed.iter().take(8).rev().fold(0, |acc, &b| acc << 8 | b as u64);
+        registry::expand_seed(base, seed_bytes * streams as usize)
+    } else {
+        Vec::new()
+    };
+    for i in 0..streams {
+        let mut seed = seed.to_vec();
+        let rng = match strategy {
+            Strategy::Adjacent => {
+                add_le(&mut seed, i as u64);
+                (generator.from_seed)(&seed)
+            }
+            Strategy::SplitMix => {
+                let start = i as usize * seed_bytes;
+                (generator.from_seed)(&splitmix[start..start + seed_bytes])
+            }
+            Strategy::Stream => {
+                let offset = generator.stream_offset
+                    .ok_or("this RNG has no stream constant or key")?;
+                add_le(&mut seed[offset..offset + 8], 2 * i as u64);
+                (generator.from_seed)(&seed)
+            }
+            Strategy::Jump => {
+                let jumped = generator.jumped.ok_or("this RNG has no jump function")?;
+                jumped(&seed, i)
+            }
+        };
+        rngs.push(rng);
+    }
+    Ok(rngs)
+}
+
+/// Add `x` to `bytes`, read as a little-endian integer, wrapping on overflow.
+pub fn add_le(bytes: &mut [u8], x: u64) {
+    let mut carry = x;
+    for byte in bytes.iter_mut() {
+        let sum = (carry & 0xff) + *byte as u64;
+        *byte = sum as u8;
+        carry = (carry >> 8) + (sum >> 8);
+    }
+}
+
+/// The outputs of several instances, interleaved per native word.
+pub struct Interleaved {
+    rngs: Vec<Box<dyn RngCore>>,
+    word_bits: u32,
+    next: usize,
+}
+
+impl Interleaved {
+    /// Interleave the words of `rngs`, which have `word_bits`-bit words.
+    pub fn new(rngs: Vec<Box<dyn RngCore>>, word_bits: u32) -> Interleaved {
+        assert!(!rngs.is_empty());
+        Interleaved { rngs, word_bits, next: 0 }
+    }
+
+    fn next_word(&mut self) -> u64 {
+        let i = self.next;
+        self.next = (i + 1) % self.rngs.len();
+        let rng = &mut self.rngs[i];
+        if self.word_bits == 32 { rng.next_u32() as u64 } else { rng.next_u64() }
+    }
+
+    /// Fill `dest` with the interleaved words. 32-bit words are packed two at
+    /// a time, the first in the low half.
+    pub fn fill(&mut self, dest: &mut [u64]) {
+        for x in dest.iter_mut() {
+            *x = if self.word_bits == 32 {
+                let low = self.next_word();
+                low | self.next_word() << 32
+            } else {
+                self.next_word()
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use {PcgXsl64LcgRng, Sfc64Rng, Xoroshiro128PlusRng};
+    use super::*;
+
+    /// The first outputs of every instance.
+    fn first_words(rngs: &mut [Box<dyn RngCore>]) -> Vec<[u64; 4]> {
+        rngs.iter_mut().map(|rng| {
+            let mut words = [0u64; 4];
+            for x in words.iter_mut() {
+                *x = rng.next_u64();
+            }
+            words
+        }).collect()
+    }
+
+    #[test]
+    fn test_strategies_distinct() {
+        for &strategy in Strategy::ALL.iter() {
+            let name = match strategy {
+                Strategy::Stream => "pcg_xsl_64_lcg",
+                Strategy::Jump => "xoroshiro_128_plus",
+                _ => "sfc_64",
+            };
+            let generator = registry::find(name).unwrap();
+            assert!(strategy.supports(generator));
+            let seed = registry::expand_seed(0, generator.seed_bytes);
+            let mut rngs = make_rngs(generator, &seed, 8, strategy).unwrap();
+            assert_eq!(rngs.len(), 8);
+            let words = first_words(&mut rngs);
+            for i in 0..words.len() {
+                for j in 0..i {
+                    assert!(words[i] != words[j], "{} and {} with {}",
+                            i, j, strategy.as_str());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_adjacent_single() {
+        let generator = registry::find("sfc_64").unwrap();
+        let seed = registry::expand_seed(1, generator.seed_bytes);
+        let mut rngs = make_rngs(generator, &seed, 1, Strategy::Adjacent).unwrap();
+        let mut seed_array = [0u8; 24];
+        seed_array.copy_from_slice(&seed);
+        let mut rng = Sfc64Rng::from_seed(seed_array);
+        for _ in 0..16 {
+            assert_eq!(rngs[0].next_u64(), rng.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_stream_offset() {
+        // Instance i has the increment of instance 0 plus 2i.
+        let generator = registry::find("pcg_xsl_64_lcg").unwrap();
+        let seed = registry::expand_seed(2, generator.seed_bytes);
+        let mut rngs = make_rngs(generator, &seed, 3, Strategy::Stream).unwrap();
+        for (i, rng) in rngs.iter_mut().enumerate() {
+            let mut seed_array = [0u8; 16];
+            seed_array.copy_from_slice(&seed);
+            add_le(&mut seed_array[8..], 2 * i as u64);
+            let mut expected = PcgXsl64LcgRng::from_seed(seed_array);
+            for _ in 0..16 {
+                assert_eq!(rng.next_u32(), expected.next_u32());
+            }
+        }
+    }
+
+    #[test]
+    fn test_jump() {
+        let generator = registry::find("xoroshiro_128_plus").unwrap();
+        let seed = registry::expand_seed(3, generator.seed_bytes);
+        let mut rngs = make_rngs(generator, &seed, 4, Strategy::Jump).unwrap();
+        let mut seed_array = [0u8; 16];
+        seed_array.copy_from_slice(&seed);
+        let mut base = Xoroshiro128PlusRng::from_seed(seed_array);
+        for rng in rngs.iter_mut() {
+            let mut expected = base.clone();
+            for _ in 0..16 {
+                assert_eq!(rng.next_u64(), expected.next_u64());
+            }
+            base.jump();
+        }
+    }
+
+    #[test]
+    fn test_interleaved() {
+        for &word_bits in [32, 64].iter() {
+            let new = |i| Box::new(Sfc64Rng::seed_from_u64(i)) as Box<dyn RngCore>;
+            let mut interleaved = Interleaved::new(vec![new(0), new(1), new(2)], word_bits);
+            let mut rngs = [Sfc64Rng::seed_from_u64(0), Sfc64Rng::seed_from_u64(1),
+                            Sfc64Rng::seed_from_u64(2)];
+            let mut words = [0u64; 10];
+            interleaved.fill(&mut words);
+            let mut expected = Vec::new();
+            for i in 0..20 {
+                let rng = &mut rngs[i % 3];
+                expected.push(if word_bits == 32 { rng.next_u32() as u64 }
+                              else { rng.next_u64() });
+            }
+            for (i, &x) in words.iter().enumerate() {
+                if word_bits == 32 {
+                    assert_eq!(x, expected[2 * i] | expected[2 * i + 1] << 32);
+                } else {
+                    assert_eq!(x, expected[i]);
+                }
+            }
+        }
+    }
+}
@@ -9,7 +9,9 @@
 
 //! The Velox 3b random number generator.
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
 
 /// A small random number generator designed by Elias Yarrkov.
 ///
@@ -31,33 +33,34 @@ impl Velox3bRng {
     fn update(&mut self) {
         self.v[0] = (self.v[0].wrapping_add(self.v[3])).rotate_left(21);
         self.v[1] = self.v[1].rotate_left(12).wrapping_add(self.v[2]);
-        self.v[2] = self.v[2] ^ self.v[0];
-        self.v[3] = self.v[3] ^ self.v[1];
+        self.v[2] ^= self.v[0];
+        self.v[3] ^= self.v[1];
 
         self.v[0] = (self.v[0].wrapping_add(self.v[3])).rotate_left(19);
         self.v[1] = self.v[1].rotate_left(24).wrapping_add(self.v[2]);
-        self.v[2] = self.v[2] ^ self.v[0];
-        self.v[3] = self.v[3] ^ self.v[1];
+        self.v[2] ^= self.v[0];
+        self.v[3] ^= self.v[1];
 
         self.v[0] = (self.v[0].wrapping_add(self.v[3])).rotate_left(7);
         self.v[1] = self.v[1].rotate_left(12).wrapping_add(self.v[2]);
-        self.v[2] = self.v[2] ^ self.v[0];
-        self.v[3] = self.v[3] ^ self.v[1];
+        self.v[2] ^= self.v[0];
+        self.v[3] ^= self.v[1];
 
         self.v[0] = (self.v[0].wrapping_add(self.v[3])).rotate_left(27);
         self.v[1] = self.v[1].rotate_left(17).wrapping_add(self.v[2]);
-        self.v[2] = self.v[2] ^ self.v[0];
-        self.v[3] = self.v[3] ^ self.v[1];
+        self.v[2] ^= self.v[0];
+        self.v[3] ^= self.v[1];
 
-        for i in 0..4 {
-            self.v[i] += self.ctr[i];
+        for (v, &ctr) in self.v.iter_mut().zip(self.ctr.iter()) {
+            *v = v.wrapping_add(ctr);
         }
 
         // increase counter by 1
-        for i in 0..4 {
-            if { self.ctr[i] = self.ctr[i].wrapping_add(1); self.ctr[i] != 0 } {
-                break
-            };
+        for ctr in self.ctr.iter_mut() {
+            *ctr = ctr.wrapping_add(1);
+            if *ctr != 0 {
+                break;
+            }
         }
 
         self.pos = 4;
@@ -68,6 +71,19 @@ impl SeedableRng for Velox3bRng {
     type Seed = [u8; 4];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Velox3bRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 16,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 1];
         le::read_u32_into(&seed, &mut seed_u32);
 
@@ -77,14 +93,14 @@ impl SeedableRng for Velox3bRng {
             // 1*0x9e3779b9, 2*0x9e3779b9, 3*0x9e3779b9, 4*0x9e3779b9
             pos: 0};
 
-        for _ in 0..16 {
+        for _ in 0..policy.warm_up {
             state.next_u32();
         }
         state
     }
 }
 
-impl Rng for Velox3bRng {
+impl RngCore for Velox3bRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         if self.pos == 0 {
@@ -99,16 +115,40 @@ impl Rng for Velox3bRng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
+}
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+impl FillWords for Velox3bRng {
+    fn fill_u32(&mut self, dest: &mut [u32]) {
+        // Use up the buffered results first, then copy whole blocks. Results
+        // are read from the end of the buffer to the front.
+        let mut left = dest;
+        while self.pos > 0 && !left.is_empty() {
+            let (first, rest) = {left}.split_at_mut(1);
+            first[0] = self.next_u32();
+            left = rest;
+        }
+        let mut chunks = left.chunks_mut(4);
+        for chunk in &mut chunks {
+            if chunk.len() < 4 {
+                for x in chunk.iter_mut() {
+                    *x = self.next_u32();
+                }
+                break;
+            }
+            self.update();
+            chunk[0] = self.v[3];
+            chunk[1] = self.v[2];
+            chunk[2] = self.v[1];
+            chunk[3] = self.v[0];
+            self.pos = 0;
+        }
     }
 }
@@ -9,7 +9,10 @@
 
 //! Xoroshiro+ random number generators
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords, LaneBlocks};
+use f2_linear::F2LinearRng;
 
 /// The Xoroshiro128+ random number generator.
 ///
@@ -26,22 +29,61 @@ pub struct Xoroshiro128PlusRng {
     s1: u64,
 }
 
+impl Xoroshiro128PlusRng {
+    /// Jump forward, equivalently to 2<sup>64</sup> calls to `next_u64`.
+    ///
+    /// This can be used to generate 2<sup>64</sup> non-overlapping
+    /// subsequences for parallel computations.
+    pub fn jump(&mut self) {
+        const JUMP: [u64; 2] = [0xbeac0467eba5facb, 0xd86b048b86aa9922];
+        let mut s0 = 0;
+        let mut s1 = 0;
+        for j in JUMP.iter() {
+            for b in 0..64 {
+                if (j >> b) & 1 != 0 {
+                    s0 ^= self.s0;
+                    s1 ^= self.s1;
+                }
+                self.next_u64();
+            }
+        }
+        self.s0 = s0;
+        self.s1 = s1;
+    }
+}
+
 impl SeedableRng for Xoroshiro128PlusRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Xoroshiro128PlusRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
 
-        if seed_u64.iter().all(|&x| x == 0) {
-            seed_u64 = [0x0DD_B1A5E5_BAD_5EED, 0x0DD_B1A5E5_BAD_5EED];
+        if policy.check_bad_state && seed_u64.iter().all(|&x| x == 0) {
+            seed_u64 = [seed_policy::BAD_SEED_REPLACEMENT; 2];
         }
 
-        Self { s0: seed_u64[0], s1: seed_u64[1] }
+        let mut state = Self { s0: seed_u64[0], s1: seed_u64[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
+        state
     }
 }
 
-impl Rng for Xoroshiro128PlusRng {
+impl RngCore for Xoroshiro128PlusRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         (self.next_u64() >> 32) as u32
@@ -60,17 +102,37 @@ impl Rng for Xoroshiro128PlusRng {
         result
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Xoroshiro128PlusRng {}
+
+impl F2LinearRng for Xoroshiro128PlusRng {
+    const STATE_BITS: u32 = 128;
+    const WORD_BITS: u32 = 64;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 64
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u64,
+               s1: (state >> 64) as u64 }
+    }
+
+    fn step(&mut self) {
+        self.next_u64();
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn next_word(&mut self) -> u64 {
+        self.next_u64()
     }
 }
 
@@ -86,18 +148,34 @@ impl SeedableRng for Xoroshiro64PlusRng {
     type Seed = [u8; 8];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Xoroshiro64PlusRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 2];
         le::read_u32_into(&seed, &mut seed_u32);
 
-        if seed_u32.iter().all(|&x| x == 0) {
+        if policy.check_bad_state && seed_u32.iter().all(|&x| x == 0) {
             seed_u32 = [0xBAD_5EED, 0xBAD_5EED];
         }
 
-        Self { s0: seed_u32[0], s1: seed_u32[1] }
+        let mut state = Self { s0: seed_u32[0], s1: seed_u32[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u32();
+        }
+        state
     }
 }
 
-impl Rng for Xoroshiro64PlusRng {
+impl RngCore for Xoroshiro64PlusRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         let s0 = self.s0;
@@ -116,16 +194,176 @@ impl Rng for Xoroshiro64PlusRng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u32(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Xoroshiro64PlusRng {}
+
+impl F2LinearRng for Xoroshiro64PlusRng {
+    const STATE_BITS: u32 = 64;
+    const WORD_BITS: u32 = 32;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 32
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u32,
+               s1: (state >> 32) as u32 }
+    }
+
+    fn step(&mut self) {
+        self.next_u32();
+    }
+
+    fn next_word(&mut self) -> u64 {
+        self.next_u32() as u64
+    }
+}
+
+
+
+/// Four independent `Xoroshiro128PlusRng` streams, advanced in lock-step.
+///
+/// The state is stored as one array per variable, so the compiler can keep all
+/// four lanes in vector registers. Lane `i` produces exactly the same outputs
+/// as the `Xoroshiro128PlusRng` it was constructed from.
+///
+/// The `RngCore` methods return lane 0, 1, 2 and 3 of every step in turn.
+///
+/// The seed is the seeds of the four lanes, one after the other.
+#[derive(Clone)]
+pub struct Xoroshiro128Plusx4Rng {
+    s0: [u64; 4],
+    s1: [u64; 4],
+    results: [u64; 4],
+    index: usize,
+}
+
+impl Xoroshiro128Plusx4Rng {
+    /// Combine four scalar generators into one multi-lane generator.
+    pub fn from_lanes(lanes: [Xoroshiro128PlusRng; 4]) -> Self {
+        let mut state = Self { s0: [0; 4], s1: [0; 4], results: [0; 4], index: 4 };
+        for (i, lane) in lanes.iter().enumerate() {
+            state.s0[i] = lane.s0;
+            state.s1[i] = lane.s1;
+        }
+        state
+    }
+
+    /// Advance all lanes by one step, and return the output of each lane.
+    #[inline]
+    pub fn next_u64x4(&mut self) -> [u64; 4] {
+        let mut result = [0u64; 4];
+        for (i, out) in result.iter_mut().enumerate() {
+            let s0 = self.s0[i];
+            let s1 = self.s1[i] ^ s0;
+            *out = s0.wrapping_add(self.s1[i]);
+            self.s0[i] = s0.rotate_left(55) ^ s1 ^ (s1 << 14); // a, b
+            self.s1[i] = s1.rotate_left(36); // c
+        }
+        result
+    }
+}
+
+/// The seed of `Xoroshiro128Plusx4Rng`: the seeds of the four
+/// `Xoroshiro128PlusRng` lanes.
+#[derive(Clone)]
+pub struct Xoroshiro128Plusx4Seed(pub [u8; 64]);
+
+impl Default for Xoroshiro128Plusx4Seed {
+    fn default() -> Self {
+        Xoroshiro128Plusx4Seed([0; 64])
+    }
+}
+
+impl AsMut<[u8]> for Xoroshiro128Plusx4Seed {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl SeedableRng for Xoroshiro128Plusx4Rng {
+    type Seed = Xoroshiro128Plusx4Seed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let lane = |i: usize| {
+            let mut lane_seed = [0u8; 16];
+            lane_seed.copy_from_slice(&seed.0[i * 16..(i + 1) * 16]);
+            Xoroshiro128PlusRng::from_seed(lane_seed)
+        };
+        Self::from_lanes([lane(0), lane(1), lane(2), lane(3)])
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl LaneBlocks for Xoroshiro128Plusx4Rng {
+    type Word = u64;
+    type Block = [u64; 4];
+
+    #[inline]
+    fn next_block(&mut self) -> [u64; 4] {
+        self.next_u64x4()
+    }
+
+    #[inline]
+    fn buffer(&mut self) -> (&mut [u64; 4], &mut usize) {
+        (&mut self.results, &mut self.index)
+    }
+}
+
+impl RngCore for Xoroshiro128Plusx4Rng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        fill::next_word_via_blocks(self)
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_blocks(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Xoroshiro128Plusx4Rng {
+    fn fill_u64(&mut self, dest: &mut [u64]) {
+        fill::fill_words_via_blocks(self, dest)
     }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::{RngCore, SeedableRng};
+    use fill;
+    use super::*;
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    #[test]
+    fn test_xoroshiro128plusx4_lanes() {
+        let mut seed = [0u8; 64];
+        fill::lanes_test_seed(&mut seed);
+        let lanes: Vec<Xoroshiro128PlusRng> = seed.chunks(16).map(|chunk| {
+            let mut lane_seed = [0u8; 16];
+            lane_seed.copy_from_slice(chunk);
+            Xoroshiro128PlusRng::from_seed(lane_seed)
+        }).collect();
+        fill::check_lanes(Xoroshiro128Plusx4Rng::from_seed(Xoroshiro128Plusx4Seed(seed)), lanes, |lane| lane.next_u64());
     }
 }
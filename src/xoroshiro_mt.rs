@@ -9,7 +9,10 @@
 
 //! Xorshift* random number generators
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
+use f2_linear::F2LinearRng;
 
 #[derive(Clone)]
 pub struct XoroshiroMt32of128Rng {
@@ -17,22 +20,61 @@ pub struct XoroshiroMt32of128Rng {
     s1: u64,
 }
 
+impl XoroshiroMt32of128Rng {
+    /// Jump forward, equivalently to 2<sup>64</sup> calls to `next_u32`.
+    ///
+    /// This can be used to generate 2<sup>64</sup> non-overlapping
+    /// subsequences for parallel computations.
+    pub fn jump(&mut self) {
+        const JUMP: [u64; 2] = [0xbeac0467eba5facb, 0xd86b048b86aa9922];
+        let mut s0 = 0;
+        let mut s1 = 0;
+        for j in JUMP.iter() {
+            for b in 0..64 {
+                if (j >> b) & 1 != 0 {
+                    s0 ^= self.s0;
+                    s1 ^= self.s1;
+                }
+                self.next_u32();
+            }
+        }
+        self.s0 = s0;
+        self.s1 = s1;
+    }
+}
+
 impl SeedableRng for XoroshiroMt32of128Rng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for XoroshiroMt32of128Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
 
-        if seed_u64.iter().all(|&x| x == 0) {
-            seed_u64 = [0x0DD_B1A5E5_BAD_5EED, 0x0DD_B1A5E5_BAD_5EED];
+        if policy.check_bad_state && seed_u64.iter().all(|&x| x == 0) {
+            seed_u64 = [seed_policy::BAD_SEED_REPLACEMENT; 2];
         }
 
-        Self { s0: seed_u64[0], s1: seed_u64[1] }
+        let mut state = Self { s0: seed_u64[0], s1: seed_u64[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u32();
+        }
+        state
     }
 }
 
-impl Rng for XoroshiroMt32of128Rng {
+impl RngCore for XoroshiroMt32of128Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         let s0 = self.s0;
@@ -50,17 +92,37 @@ impl Rng for XoroshiroMt32of128Rng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for XoroshiroMt32of128Rng {}
+
+impl F2LinearRng for XoroshiroMt32of128Rng {
+    const STATE_BITS: u32 = 128;
+    const WORD_BITS: u32 = 32;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 64
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u64,
+               s1: (state >> 64) as u64 }
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn step(&mut self) {
+        self.next_u32();
+    }
+
+    fn next_word(&mut self) -> u64 {
+        self.next_u32() as u64
     }
 }
 
@@ -72,27 +134,66 @@ pub struct XoroshiroMt64of128Rng {
     s1: u64,
 }
 
+impl XoroshiroMt64of128Rng {
+    /// Jump forward, equivalently to 2<sup>64</sup> calls to `next_u64`.
+    ///
+    /// This can be used to generate 2<sup>64</sup> non-overlapping
+    /// subsequences for parallel computations.
+    pub fn jump(&mut self) {
+        const JUMP: [u64; 2] = [0xbeac0467eba5facb, 0xd86b048b86aa9922];
+        let mut s0 = 0;
+        let mut s1 = 0;
+        for j in JUMP.iter() {
+            for b in 0..64 {
+                if (j >> b) & 1 != 0 {
+                    s0 ^= self.s0;
+                    s1 ^= self.s1;
+                }
+                self.next_u32();
+            }
+        }
+        self.s0 = s0;
+        self.s1 = s1;
+    }
+}
+
 impl SeedableRng for XoroshiroMt64of128Rng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for XoroshiroMt64of128Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
 
-        if seed_u64.iter().all(|&x| x == 0) {
-            seed_u64 = [0x0DD_B1A5E5_BAD_5EED, 0x0DD_B1A5E5_BAD_5EED];
+        if policy.check_bad_state && seed_u64.iter().all(|&x| x == 0) {
+            seed_u64 = [seed_policy::BAD_SEED_REPLACEMENT; 2];
         }
 
-        Self { s0: seed_u64[0], s1: seed_u64[1] }
+        let mut state = Self { s0: seed_u64[0], s1: seed_u64[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
+        state
     }
 }
 
-impl Rng for XoroshiroMt64of128Rng {
+impl RngCore for XoroshiroMt64of128Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         let s0 = self.s0;
         let mut s1 = self.s1;
-        let mult = (s0 as u32 as u64).wrapping_mul(3857418925 as u64);
+        let mult = (s0 as u32 as u64).wrapping_mul(3857418925u64);
 
         s1 ^= s0;
         self.s0 = s0.rotate_left(55) ^ s1 ^ (s1 << 14); // a, b
@@ -101,49 +202,57 @@ impl Rng for XoroshiroMt64of128Rng {
     }
 
     #[inline]
-    #[cfg(not(any(target_pointer_width = "32", not(feature = "i128_support"))))]
     fn next_u64(&mut self) -> u64 {
         let s0 = self.s0;
         let mut s1 = self.s1;
-        let mult = s0 as u128 * 2685821657736338717 as u128;
+        let (high, low) = s0.wmul(2685821657736338717);
 
         s1 ^= s0;
         self.s0 = s0.rotate_left(55) ^ s1 ^ (s1 << 14); // a, b
         self.s1 = s1.rotate_left(36); // c
 
-        (mult >> 32) as u64
+        high << 32 | low >> 32
     }
 
-    #[inline]
-    #[cfg(any(target_pointer_width = "32", not(feature = "i128_support")))]
-    fn next_u64(&mut self) -> u64 {
-        let s0 = self.s0;
-        let mut s1 = self.s1;
-        let (high, low) = s0.wmul(2685821657736338717);
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u64(self, dest)
+    }
 
-        s1 ^= s0;
-        self.s0 = s0.rotate_left(55) ^ s1 ^ (s1 << 14); // a, b
-        self.s1 = s1.rotate_left(36); // c
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
 
-        high << 32 | low >> 32
+impl FillWords for XoroshiroMt64of128Rng {}
+
+impl F2LinearRng for XoroshiroMt64of128Rng {
+    const STATE_BITS: u32 = 128;
+    const WORD_BITS: u32 = 64;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 64
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u64,
+               s1: (state >> 64) as u64 }
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u64(self, dest)
+    fn step(&mut self) {
+        self.next_u64();
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn next_word(&mut self) -> u64 {
+        self.next_u64()
     }
 }
 
 
 
+/// Multiply to the full width, and return the high and low half of the
+/// result. On 32-bit targets the 64-bit version does not use `u128`, because
+/// a 128-bit multiply is slow there.
 trait WideningMultiply<RHS = Self> {
     type Output;
 
@@ -165,10 +274,10 @@ macro_rules! wmul_impl {
 }
 
 wmul_impl! { u32, u64, 32 }
-#[cfg(not(any(target_pointer_width = "32", not(feature = "i128_support"))))]
+#[cfg(not(target_pointer_width = "32"))]
 wmul_impl! { u64, u128, 64 }
 
-#[cfg(any(target_pointer_width = "32", not(feature = "i128_support")))]
+#[cfg(target_pointer_width = "32")]
 impl WideningMultiply for u64 {
     type Output = (u64, u64);
 
@@ -9,7 +9,10 @@
 
 //! Plain Xorshift rondom number generators
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
+use f2_linear::F2LinearRng;
 use core::fmt;
 
 /// An Xorshift random number generator (128/32-bit variant).
@@ -17,7 +20,7 @@ use core::fmt;
 /// - Author: George Marsaglia
 /// - License: Public domain
 /// - Source: ["Xorshift RNGs"](http://www.jstatsoft.org/v08/i14/paper).
-///           *Journal of Statistical Software*. Vol. 8 (Issue 14).
+///   *Journal of Statistical Software*. Vol. 8 (Issue 14).
 /// - Period: 2<sup>128</sup> - 1
 /// - State: 128 bits
 /// - Word size: 32 bits
@@ -43,36 +46,41 @@ impl SeedableRng for Xorshift128_32Rng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Xorshift128_32Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 4];
         le::read_u32_into(&seed, &mut seed_u32);
 
-        if seed_u32.iter().all(|&x| x == 0) {
+        if policy.check_bad_state && seed_u32.iter().all(|&x| x == 0) {
             seed_u32 = [0xBAD_5EED, 0xBAD_5EED, 0xBAD_5EED, 0xBAD_5EED];
         }
 
-        Self {
+        let mut state = Self {
             x: seed_u32[0],
             y: seed_u32[1],
             z: seed_u32[2],
             w: seed_u32[3],
+        };
+        for _ in 0..policy.warm_up {
+            state.next_u32();
         }
+        state
     }
 }
 
-impl Rng for Xorshift128_32Rng {
+impl RngCore for Xorshift128_32Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
-/*
-        // optimized version
-        let x = self.x;
-        // already do the first xorshift step of the next round
-        self.x = self.y ^ (self.y << 11);
-        self.y = self.z;
-        self.z = self.z;
-        let t = self.w;
-        self.w = (x ^ (x >> 19)) ^ t ^ (t >> 8);
-        self.w
-*/
         let x = self.x;
         let t = x ^ (x << 11);
         self.x = self.y;
@@ -86,17 +94,41 @@ impl Rng for Xorshift128_32Rng {
     fn next_u64(&mut self) -> u64 {
         impls::next_u64_via_u32(self)
     }
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
     
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest);
+        fill::fill_bytes_via_u32(self, dest);
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Xorshift128_32Rng {}
+
+impl F2LinearRng for Xorshift128_32Rng {
+    const STATE_BITS: u32 = 128;
+    const WORD_BITS: u32 = 32;
+
+    fn state(&self) -> u128 {
+        (self.x as u128) | (self.y as u128) << 32 |
+        (self.z as u128) << 64 | (self.w as u128) << 96
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { x: state as u32,
+               y: (state >> 32) as u32,
+               z: (state >> 64) as u32,
+               w: (state >> 96) as u32 }
+    }
+
+    fn step(&mut self) {
+        self.next_u32();
+    }
+
+    fn next_word(&mut self) -> u64 {
+        self.next_u32() as u64
     }
 }
 
@@ -105,7 +137,7 @@ impl Rng for Xorshift128_32Rng {
 /// - Author: George Marsaglia
 /// - License: Public domain
 /// - Source: ["Xorshift RNGs"](http://www.jstatsoft.org/v08/i14/paper).
-///           *Journal of Statistical Software*. Vol. 8 (Issue 14).
+///   *Journal of Statistical Software*. Vol. 8 (Issue 14).
 /// - Period: 2<sup>128</sup> - 1
 /// - State: 128 bits
 /// - Word size: 64 bits
@@ -128,18 +160,34 @@ impl SeedableRng for Xorshift128_64Rng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Xorshift128_64Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
 
-        if seed_u64.iter().all(|&x| x == 0) {
-            seed_u64 = [0x0DD_B1A5E5_BAD_5EED, 0x0DD_B1A5E5_BAD_5EED];
+        if policy.check_bad_state && seed_u64.iter().all(|&x| x == 0) {
+            seed_u64 = [seed_policy::BAD_SEED_REPLACEMENT; 2];
         }
 
-        Self { s0: seed_u64[0], s1: seed_u64[1] }
+        let mut state = Self { s0: seed_u64[0], s1: seed_u64[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
+        state
     }
 }
 
-impl Rng for Xorshift128_64Rng {
+impl RngCore for Xorshift128_64Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -155,16 +203,36 @@ impl Rng for Xorshift128_64Rng {
         t
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Xorshift128_64Rng {}
+
+impl F2LinearRng for Xorshift128_64Rng {
+    const STATE_BITS: u32 = 128;
+    const WORD_BITS: u32 = 64;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 64
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u64,
+               s1: (state >> 64) as u64 }
+    }
+
+    fn step(&mut self) {
+        self.next_u64();
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn next_word(&mut self) -> u64 {
+        self.next_u64()
     }
 }
@@ -9,7 +9,10 @@
 
 //! Xorshift* random number generators
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
+use f2_linear::F2LinearRng;
 
 #[derive(Clone)]
 pub struct XorshiftMt32Rng {
@@ -21,18 +24,34 @@ impl SeedableRng for XorshiftMt32Rng {
     type Seed = [u8; 8];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for XorshiftMt32Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 2];
         le::read_u32_into(&seed, &mut seed_u32);
 
-        if seed_u32.iter().all(|&x| x == 0) {
+        if policy.check_bad_state && seed_u32.iter().all(|&x| x == 0) {
             seed_u32 = [0xBAD_5EED, 0xBAD_5EED];
         }
 
-        Self { s0: seed_u32[0], s1: seed_u32[1] }
+        let mut state = Self { s0: seed_u32[0], s1: seed_u32[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u32();
+        }
+        state
     }
 }
 
-impl Rng for XorshiftMt32Rng {
+impl RngCore for XorshiftMt32Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         let x = self.s0;
@@ -48,17 +67,37 @@ impl Rng for XorshiftMt32Rng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        ::rand_core::impls::fill_bytes_via_u32(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for XorshiftMt32Rng {}
+
+impl F2LinearRng for XorshiftMt32Rng {
+    const STATE_BITS: u32 = 64;
+    const WORD_BITS: u32 = 32;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 32
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u32,
+               s1: (state >> 32) as u32 }
+    }
+
+    fn step(&mut self) {
+        self.next_u32();
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn next_word(&mut self) -> u64 {
+        self.next_u32() as u64
     }
 }
 
@@ -86,18 +125,34 @@ impl SeedableRng for XorshiftMt64Rng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for XorshiftMt64Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
 
-        if seed_u64.iter().all(|&x| x == 0) {
-            seed_u64 = [0x0DD_B1A5E5_BAD_5EED, 0x0DD_B1A5E5_BAD_5EED];
+        if policy.check_bad_state && seed_u64.iter().all(|&x| x == 0) {
+            seed_u64 = [seed_policy::BAD_SEED_REPLACEMENT; 2];
         }
 
-        Self { s0: seed_u64[0], s1: seed_u64[1] }
+        let mut state = Self { s0: seed_u64[0], s1: seed_u64[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
+        state
     }
 }
 
-impl Rng for XorshiftMt64Rng {
+impl RngCore for XorshiftMt64Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         (self.xorshift().wrapping_mul(2685821657736338717) >> 16) as u32
@@ -105,19 +160,39 @@ impl Rng for XorshiftMt64Rng {
 
     #[inline]
     fn next_u64(&mut self) -> u64 {
-        ((self.xorshift() as u128 * 2685821657736338717 as u128) >> 32) as u64
+        ((self.xorshift() as u128 * 2685821657736338717u128) >> 32) as u64
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        ::rand_core::impls::fill_bytes_via_u64(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for XorshiftMt64Rng {}
+
+impl F2LinearRng for XorshiftMt64Rng {
+    const STATE_BITS: u32 = 128;
+    const WORD_BITS: u32 = 64;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 64
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u64,
+               s1: (state >> 64) as u64 }
+    }
+
+    fn step(&mut self) {
+        self.next_u64();
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn next_word(&mut self) -> u64 {
+        self.next_u64()
     }
 }
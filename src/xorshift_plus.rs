@@ -9,15 +9,18 @@
 
 //! Xorshift+ random number generators
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
+use f2_linear::F2LinearRng;
 
 /// The Xorshift128+ random number generator.
 ///
 /// - Author: Sebastiano Vigna
 /// - License: Public domain
 /// - Source: ["Further scramblings of Marsaglia's xorshift generators"]
-///           (http://vigna.di.unimi.it/ftp/papers/xorshiftplus.pdf),
-///           [xorshift128plus.c](http://xoroshiro.di.unimi.it/xorshift128plus.c)
+///   (http://vigna.di.unimi.it/ftp/papers/xorshiftplus.pdf),
+///   [xorshift128plus.c](http://xoroshiro.di.unimi.it/xorshift128plus.c)
 /// - Period: 2<sup>128</sup> - 1
 /// - State: 128 bits
 /// - Word size: 64 bits
@@ -28,22 +31,61 @@ pub struct Xorshift128PlusRng {
     s1: u64,
 }
 
+impl Xorshift128PlusRng {
+    /// Jump forward, equivalently to 2<sup>64</sup> calls to `next_u64`.
+    ///
+    /// This can be used to generate 2<sup>64</sup> non-overlapping
+    /// subsequences for parallel computations.
+    pub fn jump(&mut self) {
+        const JUMP: [u64; 2] = [0x8a5cd789635d2dff, 0x121fd2155c472f96];
+        let mut s0 = 0;
+        let mut s1 = 0;
+        for j in JUMP.iter() {
+            for b in 0..64 {
+                if (j >> b) & 1 != 0 {
+                    s0 ^= self.s0;
+                    s1 ^= self.s1;
+                }
+                self.next_u64();
+            }
+        }
+        self.s0 = s0;
+        self.s1 = s1;
+    }
+}
+
 impl SeedableRng for Xorshift128PlusRng {
     type Seed = [u8; 16];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Xorshift128PlusRng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy::CHECK;
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 2];
         le::read_u64_into(&seed, &mut seed_u64);
 
-        if seed_u64.iter().all(|&x| x == 0) {
-            seed_u64 = [0x0DD_B1A5E5_BAD_5EED, 0x0DD_B1A5E5_BAD_5EED];
+        if policy.check_bad_state && seed_u64.iter().all(|&x| x == 0) {
+            seed_u64 = [seed_policy::BAD_SEED_REPLACEMENT; 2];
         }
 
-        Self { s0: seed_u64[0], s1: seed_u64[1] }
+        let mut state = Self { s0: seed_u64[0], s1: seed_u64[1] };
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
+        state
     }
 }
 
-impl Rng for Xorshift128PlusRng {
+impl RngCore for Xorshift128PlusRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -62,16 +104,36 @@ impl Rng for Xorshift128PlusRng {
         result
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl FillWords for Xorshift128PlusRng {}
+
+impl F2LinearRng for Xorshift128PlusRng {
+    const STATE_BITS: u32 = 128;
+    const WORD_BITS: u32 = 64;
+
+    fn state(&self) -> u128 {
+        (self.s0 as u128) | (self.s1 as u128) << 64
+    }
+
+    fn from_state(state: u128) -> Self {
+        Self { s0: state as u64,
+               s1: (state >> 64) as u64 }
+    }
+
+    fn step(&mut self) {
+        self.next_u64();
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn next_word(&mut self) -> u64 {
+        self.next_u64()
     }
 }
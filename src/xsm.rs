@@ -9,7 +9,9 @@
 
 //! The XSM random number generator.
 
-use rand_core::{Rng, SeedableRng, Error, impls, le};
+use rand_core::{RngCore, SeedableRng, Error, impls, le};
+use seed_policy::{self, SeedPolicy, SeedWithPolicy};
+use fill::{self, FillWords};
 
 /// XSM (32-bit version).
 ///
@@ -33,6 +35,19 @@ impl SeedableRng for Xsm32Rng {
     type Seed = [u8; 12];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Xsm32Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 1,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u32 = [0u32; 3];
         le::read_u32_into(&seed, &mut seed_u32);
         let mut state = Self {
@@ -41,12 +56,14 @@ impl SeedableRng for Xsm32Rng {
             lcg_adder: seed_u32[2] | 1,
             history: 0,
         };
-        state.next_u32();
+        for _ in 0..policy.warm_up {
+            state.next_u32();
+        }
         state
     }
 }
 
-impl Rng for Xsm32Rng {
+impl RngCore for Xsm32Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         const K: u32 = 0x6595a395;
@@ -71,20 +88,18 @@ impl Rng for Xsm32Rng {
         impls::next_u64_via_u32(self)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u32(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl FillWords for Xsm32Rng {}
+
 
 
 /// XSM (64-bit version).
@@ -109,6 +124,19 @@ impl SeedableRng for Xsm64Rng {
     type Seed = [u8; 24];
 
     fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_with_policy(seed, Self::SEED_POLICY)
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        seed_policy::seed_from_u64(state)
+    }
+}
+
+impl SeedWithPolicy for Xsm64Rng {
+    const SEED_POLICY: SeedPolicy = SeedPolicy { warm_up: 1,
+                                                 check_bad_state: false };
+
+    fn from_seed_with_policy(seed: Self::Seed, policy: SeedPolicy) -> Self {
         let mut seed_u64 = [0u64; 3];
         le::read_u64_into(&seed, &mut seed_u64);
         let mut state = Self {
@@ -117,12 +145,14 @@ impl SeedableRng for Xsm64Rng {
             lcg_adder: seed_u64[2] | 1,
             history: 0,
         };
-        state.next_u64();
+        for _ in 0..policy.warm_up {
+            state.next_u64();
+        }
         state
     }
 }
 
-impl Rng for Xsm64Rng {
+impl RngCore for Xsm64Rng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
@@ -145,16 +175,14 @@ impl Rng for Xsm64Rng {
         tmp.wrapping_add(old)
     }
 
-    #[cfg(feature = "i128_support")]
-    fn next_u128(&mut self) -> u128 {
-        impls::next_u128_via_u64(self)
-    }
-
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_u32(self, dest)
+        fill::fill_bytes_via_u64(self, dest)
     }
 
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
+
+impl FillWords for Xsm64Rng {}